@@ -0,0 +1,125 @@
+use crypto::digest::Digest;
+use crypto::sha2::Sha256;
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+/// Size of the blocks that are hashed separately
+pub const BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Incremental hasher for the `content_hash` dropbox gives files
+///
+/// The contents are split into 4MB blocks, each block is hashed with SHA-256, and the hex
+/// encoded SHA-256 of all the block hashes joined together is the content hash. Data can be fed
+/// in pieces of any size, either through `update` or by writing to the hasher.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::fs::File;
+/// use std::io;
+/// use dbox::content_hash::ContentHasher;
+///
+/// let mut hasher = ContentHasher::new();
+/// try!(io::copy(&mut try!(File::open("photo.jpg")), &mut hasher));
+/// let hash = hasher.finish();
+/// ```
+pub struct ContentHasher {
+    overall: Sha256,
+    block: Sha256,
+    block_len: usize,
+}
+
+impl ContentHasher {
+    pub fn new() -> ContentHasher {
+        ContentHasher {
+            overall: Sha256::new(),
+            block: Sha256::new(),
+            block_len: 0,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut data = data;
+        while !data.is_empty() {
+            let len = ::std::cmp::min(BLOCK_SIZE - self.block_len, data.len());
+            self.block.input(&data[..len]);
+            self.block_len += len;
+            data = &data[len..];
+            if self.block_len == BLOCK_SIZE {
+                self.finish_block();
+            }
+        }
+    }
+
+    /// The hex encoded content hash of everything fed to the hasher
+    pub fn finish(mut self) -> String {
+        if self.block_len > 0 {
+            self.finish_block();
+        }
+        self.overall.result_str()
+    }
+
+    fn finish_block(&mut self) {
+        let mut digest = [0; 32];
+        self.block.result(&mut digest);
+        self.overall.input(&digest);
+        self.block.reset();
+        self.block_len = 0;
+    }
+}
+
+impl Default for ContentHasher {
+    fn default() -> ContentHasher {
+        ContentHasher::new()
+    }
+}
+
+impl Write for ContentHasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Content hash of everything in `reader`
+pub fn hash_reader<R: Read>(reader: &mut R) -> io::Result<String> {
+    let mut hasher = ContentHasher::new();
+    try!(io::copy(reader, &mut hasher));
+    Ok(hasher.finish())
+}
+
+/// Content hash of a local file
+pub fn hash_file(path: &str) -> io::Result<String> {
+    hash_reader(&mut try!(File::open(path)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContentHasher, BLOCK_SIZE};
+
+    #[test]
+    fn test_content_hash() {
+        assert_eq!(ContentHasher::new().finish(),
+                   "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855");
+
+        // Hashing in pieces that straddle the block boundary must match hashing in one go
+        let data = (0..BLOCK_SIZE + 100).map(|i| i as u8).collect::<Vec<_>>();
+        let mut whole = ContentHasher::new();
+        whole.update(&data);
+        let mut pieces = ContentHasher::new();
+        for piece in data.chunks(999) {
+            pieces.update(piece);
+        }
+        let whole = whole.finish();
+        assert_eq!(whole, "d11d01f5f71ec69c32864eefce519bce41e4bc40d35a824348981c863efefd09");
+        assert_eq!(whole, pieces.finish());
+
+        let mut hasher = ContentHasher::new();
+        hasher.update(b"abc");
+        assert_eq!(hasher.finish(), "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358");
+    }
+}
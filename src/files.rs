@@ -1,37 +1,126 @@
-use super::{Result, Response, DropboxClient, ApiError};
+use super::{Result, Response, DropboxClient, ApiError, Body, route_error, DATE_FORMAT};
 use std::default::Default;
 use std::io::{self, Write, Read};
+use std::cmp;
 use std::fmt;
-use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::vec;
+use std::time::Duration;
+use std::collections::{BTreeMap, BTreeSet};
+use chrono::{DateTime, UTC};
+use filetime::{self, FileTime};
 use rustc_serialize::json;
+use rustc_serialize::{Decoder, Decodable, Encodable};
+use rustc_serialize::json::ToJson;
 
-use structs::{FolderList, Metadata, FileMetadata, NewFolder};
+use content_hash::{self, ContentHasher};
+use structs::{FolderList, Metadata, FileMetadata, DeletedMetadata, NewFolder};
+
+/// How many range requests `download_to_file` makes before giving up on a dropped download
+const DOWNLOAD_ATTEMPTS: u32 = 5;
+/// How many times a chunk of an upload session is sent before giving up on it
+const CHUNK_ATTEMPTS: u32 = 5;
+/// All but the last chunk of a concurrent upload session must be a multiple of this size
+const CONCURRENT_CHUNK_ALIGN: usize = 4 * 1024 * 1024;
+/// Size of the buffer downloads are copied through, which is how often they report progress
+const COPY_BUFFER_SIZE: usize = 64 * 1024;
+/// Most that dropbox accepts in a single `upload` or upload session request
+const UPLOAD_LIMIT: usize = 150 * 1024 * 1024;
+/// How long a `Watcher` longpolls for, in seconds, unless told otherwise; also the shortest
+/// timeout dropbox accepts
+const LONGPOLL_TIMEOUT: usize = 30;
+/// Longest timeout dropbox accepts for a longpoll, in seconds
+const LONGPOLL_MAX_TIMEOUT: usize = 480;
+/// How long `upload_session_finish_batch_wait` waits between checks on the job
+const BATCH_POLL_INTERVAL_MS: u64 = 1000;
+/// How many times `upload_session_finish_batch_wait` checks on the job before giving up
+const BATCH_POLL_ATTEMPTS: u32 = 600;
+/// Most sessions `upload_session_finish_batch` can commit at once
+const BATCH_MAX_ENTRIES: usize = 1000;
 
 /// Instructs dropbox what to do when a conflict happens during upload
 #[derive(Debug, PartialEq, Clone)]
 pub enum WriteMode {
+    /// Never overwrite an existing file
     Add,
+    /// Always overwrite an existing file
     Overwrite,
-    Update,
+    /// Overwrite the file only if it is still at this `rev`
+    ///
+    /// If someone else changed the file, the upload fails with an `UploadError::Path` holding a
+    /// `WriteError::Conflict`.
+    Update(String),
 }
 
-impl fmt::Display for WriteMode {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+impl ToJson for WriteMode {
+    fn to_json(&self) -> json::Json {
+        let mut map = BTreeMap::new();
         match *self {
-            WriteMode::Add => write!(f, "add"),
-            WriteMode::Overwrite => write!(f, "overwrite"),
-            WriteMode::Update => write!(f, "update"),
+            WriteMode::Add => {
+                map.insert(".tag".to_string(), json::Json::String("add".to_string()));
+            },
+            WriteMode::Overwrite => {
+                map.insert(".tag".to_string(), json::Json::String("overwrite".to_string()));
+            },
+            WriteMode::Update(ref rev) => {
+                map.insert(".tag".to_string(), json::Json::String("update".to_string()));
+                map.insert("update".to_string(), json::Json::String(rev.clone()));
+            },
         }
+        json::Json::Object(map)
     }
 }
 
 /// Optional arguments to the `upload` API call
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, Clone)]
 pub struct UploadOptions {
     pub mode: WriteMode,
     pub autorename: bool,
-    pub client_modified: Option<String>,
+    /// Modification time to record for the file, instead of the time it was uploaded
+    pub client_modified: Option<DateTime<UTC>>,
     pub mute: bool,
+    /// Don't upload a file that is already at `path` with the same contents
+    ///
+    /// Only `upload_file` does this, as the local file has to be hashed before it is uploaded.
+    pub skip_unchanged: bool,
+    /// `upload_reader` and `upload_file` use an upload session for files larger than this
+    ///
+    /// Anything above the 150MB a single request can take is treated as 150MB.
+    pub upload_threshold: usize,
+    /// Size of the chunks an upload session sends, of at most 150MB
+    pub chunk_size: usize,
+    /// Progress reporting and cancellation for the upload
+    pub transfer: Transfer,
+}
+
+impl UploadOptions {
+    /// Sets `upload_threshold`, clamped to the 150MB a single request can take
+    pub fn upload_threshold(self, upload_threshold: usize) -> UploadOptions {
+        UploadOptions {
+            upload_threshold: cmp::min(upload_threshold, UPLOAD_LIMIT),
+            .. self
+        }
+    }
+
+    /// Sets `chunk_size`, clamped to between 1 byte and the 150MB a single request can take
+    pub fn chunk_size(self, chunk_size: usize) -> UploadOptions {
+        UploadOptions {
+            chunk_size: cmp::max(cmp::min(chunk_size, UPLOAD_LIMIT), 1),
+            .. self
+        }
+    }
+
+    pub fn transfer(self, transfer: Transfer) -> UploadOptions {
+        UploadOptions {
+            transfer: transfer,
+            .. self
+        }
+    }
 }
 
 impl Default for UploadOptions {
@@ -41,10 +130,96 @@ impl Default for UploadOptions {
             autorename: false,
             client_modified: None,
             mute: false,
+            skip_unchanged: false,
+            upload_threshold: 16 * 1024 * 1024,
+            chunk_size: 8 * 1024 * 1024,
+            transfer: Transfer::new(),
+        }
+    }
+}
+
+/// Progress reporting and cancellation for a transfer
+///
+/// Clones share the same cancellation flag, so a clone can be handed to another thread and
+/// cancel the transfer from there. Cancellation is cooperative: the transfer stops before its
+/// next chunk, and fails with `ApiError::Cancelled`.
+///
+/// # Example
+///
+/// ```ignore
+/// use dbox::files::Transfer;
+///
+/// let transfer = Transfer::new().on_progress(|done, total| {
+///     println!("{} of {:?} bytes", done, total);
+/// });
+/// let canceller = transfer.clone();
+/// // later, from another thread
+/// canceller.cancel();
+/// ```
+#[derive(Clone, Default)]
+pub struct Transfer {
+    progress: Option<Arc<Fn(u64, Option<u64>) + Send + Sync>>,
+    cancelled: Arc<AtomicBool>,
+    verify: bool,
+}
+
+impl Transfer {
+    pub fn new() -> Transfer {
+        Default::default()
+    }
+
+    /// Calls `progress` with the number of bytes transferred so far, and the total size when it
+    /// is known
+    pub fn on_progress<F>(self, progress: F) -> Transfer
+                where F: Fn(u64, Option<u64>) + Send + Sync + 'static
+    {
+        Transfer {
+            progress: Some(Arc::new(progress)),
+            .. self
+        }
+    }
+
+    /// Check downloads against the file's `content_hash`, failing with
+    /// `ApiError::ContentHashMismatch` if they differ
+    pub fn verify(self, verify: bool) -> Transfer {
+        Transfer {
+            verify: verify,
+            .. self
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+
+    fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(ApiError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn report(&self, done: u64, total: Option<u64>) {
+        if let Some(ref progress) = self.progress {
+            progress(done, total);
         }
     }
 }
 
+impl fmt::Debug for Transfer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Transfer")
+         .field("cancelled", &self.is_cancelled())
+         .field("verify", &self.verify)
+         .finish()
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum ThumbnailFormat {
     Jpeg,
@@ -66,6 +241,26 @@ pub struct ThumbnailOptions {
     size: ThumbnailSize,
 }
 
+impl ThumbnailOptions {
+    pub fn new() -> ThumbnailOptions {
+        Default::default()
+    }
+
+    pub fn format(self, format: ThumbnailFormat) -> ThumbnailOptions {
+        ThumbnailOptions {
+            format: format,
+            .. self
+        }
+    }
+
+    pub fn size(self, size: ThumbnailSize) -> ThumbnailOptions {
+        ThumbnailOptions {
+            size: size,
+            .. self
+        }
+    }
+}
+
 impl Default for ThumbnailOptions {
     fn default() -> ThumbnailOptions {
         ThumbnailOptions {
@@ -75,35 +270,404 @@ impl Default for ThumbnailOptions {
     }
 }
 
+/// Optional arguments to the `list_folder/get_latest_cursor` API call, which takes the same
+/// arguments as `list_folder`
+pub type GetCursorOptions = ListFolderOptions;
+
+/// Optional arguments to the `list_folder` API call
+///
+/// # Example
+///
+/// ```ignore
+/// use dbox::files::ListFolderOptions;
+///
+/// let options = ListFolderOptions::new().recursive(true).include_deleted(true).limit(500);
+/// ```
 #[derive(Debug, PartialEq, Clone)]
-pub struct GetCursorOptions {
+pub struct ListFolderOptions {
     recursive: bool,
     include_media_info: bool,
     include_deleted: bool,
+    include_has_explicit_shared_members: bool,
+    include_mounted_folders: bool,
+    limit: Option<u32>,
+    shared_link: Option<String>,
+    shared_link_password: Option<String>,
+}
+
+impl ListFolderOptions {
+    pub fn new() -> ListFolderOptions {
+        Default::default()
+    }
+
+    /// List the contents of all subfolders too
+    pub fn recursive(self, recursive: bool) -> ListFolderOptions {
+        ListFolderOptions {
+            recursive: recursive,
+            .. self
+        }
+    }
+
+    /// Include the `MediaInfo` of photos and videos in the listed `FileMetadata`
+    pub fn include_media_info(self, include_media_info: bool) -> ListFolderOptions {
+        ListFolderOptions {
+            include_media_info: include_media_info,
+            .. self
+        }
+    }
+
+    /// Include entries for files and folders that have been deleted
+    pub fn include_deleted(self, include_deleted: bool) -> ListFolderOptions {
+        ListFolderOptions {
+            include_deleted: include_deleted,
+            .. self
+        }
+    }
+
+    pub fn include_has_explicit_shared_members(self, include_has_explicit_shared_members: bool) -> ListFolderOptions {
+        ListFolderOptions {
+            include_has_explicit_shared_members: include_has_explicit_shared_members,
+            .. self
+        }
+    }
+
+    /// Include the contents of mounted folders, such as team and shared folders
+    pub fn include_mounted_folders(self, include_mounted_folders: bool) -> ListFolderOptions {
+        ListFolderOptions {
+            include_mounted_folders: include_mounted_folders,
+            .. self
+        }
+    }
+
+    /// Maximum number of entries per page; dropbox may return fewer
+    pub fn limit(self, limit: u32) -> ListFolderOptions {
+        ListFolderOptions {
+            limit: Some(limit),
+            .. self
+        }
+    }
+
+    /// List a folder behind a shared link, with the path taken relative to the link
+    pub fn shared_link(self, url: &str, password: Option<&str>) -> ListFolderOptions {
+        ListFolderOptions {
+            shared_link: Some(url.to_owned()),
+            shared_link_password: password.map(|p| p.to_owned()),
+            .. self
+        }
+    }
+
+    /// Arguments for listing `path` with these options
+    fn args(&self, path: &str) -> BTreeMap<String, json::Json> {
+        let mut map = BTreeMap::new();
+        map.insert("path".to_string(), json::Json::String(path.to_string()));
+        map.insert("recursive".to_string(), json::Json::Boolean(self.recursive));
+        map.insert("include_media_info".to_string(), json::Json::Boolean(self.include_media_info));
+        map.insert("include_deleted".to_string(), json::Json::Boolean(self.include_deleted));
+        map.insert("include_has_explicit_shared_members".to_string(),
+                   json::Json::Boolean(self.include_has_explicit_shared_members));
+        map.insert("include_mounted_folders".to_string(), json::Json::Boolean(self.include_mounted_folders));
+        if let Some(limit) = self.limit {
+            map.insert("limit".to_string(), json::Json::U64(limit as u64));
+        }
+        if let Some(ref url) = self.shared_link {
+            let mut link = BTreeMap::new();
+            link.insert("url".to_string(), json::Json::String(url.clone()));
+            if let Some(ref password) = self.shared_link_password {
+                link.insert("password".to_string(), json::Json::String(password.clone()));
+            }
+            map.insert("shared_link".to_string(), json::Json::Object(link));
+        }
+        map
+    }
 }
 
-impl Default for GetCursorOptions {
-    fn default() -> GetCursorOptions {
-        GetCursorOptions {
+impl Default for ListFolderOptions {
+    fn default() -> ListFolderOptions {
+        ListFolderOptions {
             recursive: false,
             include_media_info: false,
             include_deleted: false,
+            include_has_explicit_shared_members: false,
+            include_mounted_folders: true,
+            limit: None,
+            shared_link: None,
+            shared_link_password: None,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+/// Iterator over every entry in a folder, created by `list_folder_iter`
+///
+/// Pages are fetched with `list_folder_continue` as the entries of the previous one run out. If
+/// a request fails, its error is the last item.
+pub struct ListFolderIter<'a, T: 'a> {
+    client: &'a T,
+    path: String,
+    options: ListFolderOptions,
+    entries: vec::IntoIter<Metadata>,
+    cursor: Option<String>,
+    has_more: bool,
+}
+
+impl<'a, T: DropboxClient> ListFolderIter<'a, T> {
+    /// Cursor of the last page fetched
+    ///
+    /// Once the iterator is exhausted, this is where `list_folder_continue` picks up any later
+    /// changes to the folder.
+    pub fn cursor(&self) -> Option<&str> {
+        self.cursor.as_ref().map(|c| &c[..])
+    }
+}
+
+impl<'a, T: DropboxClient> Iterator for ListFolderIter<'a, T> {
+    type Item = Result<Metadata>;
+
+    fn next(&mut self) -> Option<Result<Metadata>> {
+        loop {
+            if let Some(entry) = self.entries.next() {
+                return Some(Ok(entry));
+            }
+            if !self.has_more {
+                return None;
+            }
+            let page = match self.cursor {
+                Some(ref cursor) => list_folder_continue(self.client, cursor),
+                None => list_folder_with_options(self.client, &self.path, self.options.clone()),
+            };
+            match page {
+                Ok(page) => {
+                    self.cursor = Some(page.cursor().to_owned());
+                    self.has_more = page.has_more();
+                    self.entries = page.into_entries().into_iter();
+                },
+                Err(e) => {
+                    self.has_more = false;
+                    return Some(Err(e));
+                },
+            }
+        }
+    }
+}
+
+/// Result of the `list_folder/longpoll` API call
+#[derive(Debug, PartialEq, Clone, RustcDecodable)]
 pub struct FolderListLongpoll {
     changes: bool,
-    backoff: Option<bool>,
+    backoff: Option<u64>,
+}
+
+impl FolderListLongpoll {
+    /// Whether there are changes to fetch with `list_folder_continue`
+    pub fn changes(&self) -> bool {
+        self.changes
+    }
+
+    /// Seconds to wait before longpolling again, if dropbox asked for a break
+    pub fn backoff(&self) -> Option<u64> {
+        self.backoff
+    }
+}
+
+/// A change to an entry of a folder, reported by a `Watcher`
+#[derive(Debug, PartialEq, Clone)]
+pub enum Change {
+    /// A new file or folder appeared
+    Added(Metadata),
+    /// A file or folder that was already there changed
+    Modified(Metadata),
+    /// A file or folder was deleted; deleting a folder deletes everything in it too
+    Deleted(DeletedMetadata),
+}
+
+/// Watches a folder for changes, using `list_folder_longpoll`
+///
+/// The watcher holds a cursor, waits on the notify endpoint until something changes, then
+/// fetches the changes with `list_folder_continue` and reports them as `Change`s. When dropbox
+/// asks for a `backoff`, the next longpoll waits that long first.
+///
+/// To tell added files from modified ones, the watcher keeps track of the entries it has seen.
+/// A watcher made with `Watcher::new` starts from a full listing of the folder; one made from
+/// an existing cursor knows nothing about the entries that were already there, so it reports
+/// files it hasn't seen before as `Modified`.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files::{self, Change, Watcher};
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let options = files::ListFolderOptions::new().recursive(true);
+/// let mut watcher = try!(Watcher::new(&client, "/path/to/folder", options));
+/// try!(watcher.watch(|change| {
+///     println!("{:?}", change);
+///     true
+/// }));
+/// ```
+pub struct Watcher<'a, T: 'a> {
+    client: &'a T,
+    cursor: String,
+    timeout: usize,
+    backoff: Option<u64>,
+    known: BTreeSet<String>,
+    complete: bool,
+}
+
+impl<'a, T: DropboxClient> Watcher<'a, T> {
+    /// Lists the folder, then watches it for changes from there on
+    pub fn new(client: &'a T, path: &str, options: ListFolderOptions) -> Result<Watcher<'a, T>> {
+        let mut entries = list_folder_iter_with_options(client, path, options);
+        let mut known = BTreeSet::new();
+        for entry in &mut entries {
+            let entry = try!(entry);
+            if let Metadata::Deleted(_) = entry {
+                continue;
+            }
+            if let Some(path) = entry.path_lower() {
+                known.insert(path.to_owned());
+            }
+        }
+        let cursor = entries.cursor().unwrap_or("").to_owned();
+        Ok(Watcher {
+            client: client,
+            cursor: cursor,
+            timeout: LONGPOLL_TIMEOUT,
+            backoff: None,
+            known: known,
+            complete: true,
+        })
+    }
+
+    /// Watches for changes since `cursor` was returned
+    pub fn from_cursor(client: &'a T, cursor: &str) -> Watcher<'a, T> {
+        Watcher {
+            client: client,
+            cursor: cursor.to_owned(),
+            timeout: LONGPOLL_TIMEOUT,
+            backoff: None,
+            known: BTreeSet::new(),
+            complete: false,
+        }
+    }
+
+    /// How long each longpoll waits for changes, in seconds
+    ///
+    /// `list_folder_longpoll` clamps this to the 30 to 480 seconds dropbox accepts.
+    pub fn timeout(self, timeout: usize) -> Watcher<'a, T> {
+        Watcher {
+            timeout: timeout,
+            .. self
+        }
+    }
+
+    /// Cursor up to which changes have been reported
+    pub fn cursor(&self) -> &str {
+        &self.cursor
+    }
+
+    /// Blocks until there are changes, and returns them
+    pub fn poll(&mut self) -> Result<Vec<Change>> {
+        loop {
+            if let Some(backoff) = self.backoff.take() {
+                thread::sleep(Duration::from_secs(backoff));
+            }
+            let result = try!(list_folder_longpoll(self.client, &self.cursor, self.timeout));
+            self.backoff = result.backoff();
+            if result.changes() {
+                let changes = try!(self.fetch_changes());
+                if !changes.is_empty() {
+                    return Ok(changes);
+                }
+            }
+        }
+    }
+
+    /// Calls `f` with every change, until it returns `false` or a request fails
+    pub fn watch<F>(&mut self, mut f: F) -> Result<()>
+                where F: FnMut(Change) -> bool
+    {
+        loop {
+            for change in try!(self.poll()) {
+                if !f(change) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn fetch_changes(&mut self) -> Result<Vec<Change>> {
+        let mut changes = vec![];
+        loop {
+            let page = try!(list_folder_continue(self.client, &self.cursor));
+            self.cursor = page.cursor().to_owned();
+            let has_more = page.has_more();
+            for entry in page.into_entries() {
+                changes.push(self.classify(entry));
+            }
+            if !has_more {
+                return Ok(changes);
+            }
+        }
+    }
+
+    fn classify(&mut self, entry: Metadata) -> Change {
+        let path = entry.path_lower().map(|path| path.to_owned());
+        let path = match path {
+            Some(path) => path,
+            // Without a path there is nothing to track the entry by, so it can't be told apart
+            // from one that was already there
+            None => {
+                return match entry {
+                    Metadata::Deleted(deleted) => Change::Deleted(deleted),
+                    entry => Change::Modified(entry),
+                };
+            },
+        };
+        match entry {
+            Metadata::Deleted(deleted) => {
+                let prefix = format!("{}/", path);
+                let removed = self.known.iter()
+                                        .filter(|known| known.starts_with(&prefix))
+                                        .cloned()
+                                        .collect::<Vec<_>>();
+                for known in removed {
+                    self.known.remove(&known);
+                }
+                self.known.remove(&path);
+                Change::Deleted(deleted)
+            },
+            entry => {
+                if self.known.insert(path) && self.complete {
+                    Change::Added(entry)
+                } else {
+                    Change::Modified(entry)
+                }
+            },
+        }
+    }
 }
 
+/// Revisions of a file, as returned by `list_revisions`
+///
+/// `list_revisions` isn't implemented yet, so there are currently never any entries.
 #[derive(Debug, PartialEq, Clone)]
 pub struct ListRevisions {
     is_deleted: bool,
     entries: Vec<Metadata>,
 }
 
+impl ListRevisions {
+    pub fn is_deleted(&self) -> bool {
+        self.is_deleted
+    }
+
+    /// Revisions of the file, newest first
+    pub fn entries(&self) -> &[Metadata] {
+        &self.entries
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum SearchMode {
     Filename,
@@ -118,6 +682,34 @@ pub struct SearchOptions {
     mode: SearchMode,
 }
 
+impl SearchOptions {
+    pub fn new() -> SearchOptions {
+        Default::default()
+    }
+
+    /// Index of the first result to return, for paging through results
+    pub fn start(self, start: usize) -> SearchOptions {
+        SearchOptions {
+            start: start,
+            .. self
+        }
+    }
+
+    pub fn max_results(self, max_results: usize) -> SearchOptions {
+        SearchOptions {
+            max_results: max_results,
+            .. self
+        }
+    }
+
+    pub fn mode(self, mode: SearchMode) -> SearchOptions {
+        SearchOptions {
+            mode: mode,
+            .. self
+        }
+    }
+}
+
 impl Default for SearchOptions {
     fn default() -> SearchOptions {
         SearchOptions {
@@ -141,6 +733,19 @@ pub struct SearchMatch {
     metadata: Metadata,
 }
 
+impl SearchMatch {
+    pub fn match_type(&self) -> &SearchMatchType {
+        &self.match_type
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+/// Results of a `search`
+///
+/// `search` isn't implemented yet, so there are currently never any matches.
 #[derive(Debug, PartialEq, Clone)]
 pub struct Search {
     matches: Vec<SearchMatch>,
@@ -148,49 +753,554 @@ pub struct Search {
     start: usize,
 }
 
+impl Search {
+    pub fn matches(&self) -> &[SearchMatch] {
+        &self.matches
+    }
+
+    /// Whether there are more results after these
+    pub fn more(&self) -> bool {
+        self.more
+    }
+
+    /// Index to pass as `SearchOptions::start` for the next page
+    pub fn start(&self) -> usize {
+        self.start
+    }
+}
+
+/// Where and how to save the file uploaded in an upload session
+///
+/// # Example
+///
+/// ```ignore
+/// let commit = CommitInfo::new("/path/to/file").mode(WriteMode::Overwrite).mute(true);
+/// ```
 #[derive(Debug, PartialEq, Clone)]
 pub struct CommitInfo {
     path: String,
     mode: WriteMode,
     autorename: bool,
-    client_modified: String,
+    client_modified: Option<DateTime<UTC>>,
     mute: bool,
 }
 
+impl CommitInfo {
+    pub fn new(path: &str) -> CommitInfo {
+        CommitInfo {
+            path: path.to_owned(),
+            mode: WriteMode::Add,
+            autorename: false,
+            client_modified: None,
+            mute: false,
+        }
+    }
+
+    pub fn mode(self, mode: WriteMode) -> CommitInfo {
+        CommitInfo {
+            mode: mode,
+            .. self
+        }
+    }
+
+    pub fn autorename(self, autorename: bool) -> CommitInfo {
+        CommitInfo {
+            autorename: autorename,
+            .. self
+        }
+    }
+
+    /// Modification time to record for the file, instead of the time it was uploaded
+    pub fn client_modified(self, client_modified: DateTime<UTC>) -> CommitInfo {
+        CommitInfo {
+            client_modified: Some(client_modified),
+            .. self
+        }
+    }
+
+    pub fn mute(self, mute: bool) -> CommitInfo {
+        CommitInfo {
+            mute: mute,
+            .. self
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+impl ToJson for CommitInfo {
+    fn to_json(&self) -> json::Json {
+        let mut map = BTreeMap::new();
+        map.insert("path".to_string(), json::Json::String(self.path.clone()));
+        map.insert("mode".to_string(), self.mode.to_json());
+        map.insert("autorename".to_string(), json::Json::Boolean(self.autorename));
+        if let Some(ref client_modified) = self.client_modified {
+            map.insert("client_modified".to_string(),
+                       json::Json::String(client_modified.format(DATE_FORMAT).to_string()));
+        }
+        map.insert("mute".to_string(), json::Json::Boolean(self.mute));
+        json::Json::Object(map)
+    }
+}
+
+/// Position in an upload session: the next chunk has to start at `offset`
 #[derive(Debug, PartialEq, Clone)]
 pub struct UploadSessionCursor {
     session_id: String,
-    offset: usize,
+    offset: u64,
 }
 
-// Functions
+impl UploadSessionCursor {
+    pub fn new(session_id: &str, offset: u64) -> UploadSessionCursor {
+        UploadSessionCursor {
+            session_id: session_id.to_owned(),
+            offset: offset,
+        }
+    }
 
-/// Copy a file
-///
-/// # Example
-///
-/// ```ignore
-/// use std::env;
-/// use dbox::client::Client;
-/// use dbox::files;
-///
-/// let client = Client::new(env::var("DROPBOX_TOKEN"));
-/// let metadata = try!(files::copy_(&client, "/Path/to/existing/file", "/Path/to/new/file"));
-/// ```
-pub fn copy_<T>(client: &T, from: &str, to: &str) -> Result<Metadata>
-                where T: DropboxClient
-{
-    let mut map = BTreeMap::new();
-    map.insert("from_path".to_string(), json::Json::String(from.to_string()));
-    map.insert("to_path".to_string(), json::Json::String(to.to_string()));
-    let mut headers = BTreeMap::new();
-    let resp = try!(client.api("files/copy", &mut headers, Some(&map)));
-    json::decode(&resp.body).map_err(|e| ApiError::from(e))
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Number of bytes uploaded to the session so far
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
 }
 
-/// Create a folder
-///
-/// # Example
+impl ToJson for UploadSessionCursor {
+    fn to_json(&self) -> json::Json {
+        let mut map = BTreeMap::new();
+        map.insert("session_id".to_string(), json::Json::String(self.session_id.clone()));
+        map.insert("offset".to_string(), json::Json::U64(self.offset));
+        json::Json::Object(map)
+    }
+}
+
+/// Outcome of committing one of the sessions passed to `upload_session_finish_batch`
+#[derive(Debug, PartialEq, Clone)]
+pub enum UploadSessionFinishBatchResultEntry {
+    Success(FileMetadata),
+    Failure(UploadSessionFinishError),
+}
+
+/// What `upload_session_finish_batch` returns: either the results, or a job to poll for them
+#[derive(Debug, PartialEq, Clone)]
+pub enum UploadSessionFinishBatchLaunch {
+    AsyncJobId(String),
+    Complete(Vec<UploadSessionFinishBatchResultEntry>),
+}
+
+/// Status of a job started by `upload_session_finish_batch`
+#[derive(Debug, PartialEq, Clone)]
+pub enum UploadSessionFinishBatchJobStatus {
+    InProgress,
+    Complete(Vec<UploadSessionFinishBatchResultEntry>),
+}
+
+#[derive(RustcDecodable)]
+struct GetLatestCursorResult {
+    cursor: String,
+}
+
+#[derive(RustcDecodable)]
+struct UploadSessionStartResult {
+    session_id: String,
+}
+
+/// Reader that keeps track of how many bytes have been read through it
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = try!(self.inner.read(buf));
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+/// Error looking up a path in the user's dropbox
+#[derive(Debug, PartialEq, Clone)]
+pub enum LookupError {
+    MalformedPath(Option<String>),
+    NotFound,
+    NotFile,
+    NotFolder,
+    RestrictedContent,
+    Other(String),
+}
+
+impl Decodable for LookupError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<LookupError, D::Error> {
+        decoder.read_struct("LookupError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "malformed_path" => LookupError::MalformedPath(try!(decoder.read_struct_field("malformed_path", 0, Decodable::decode))),
+                "not_found" => LookupError::NotFound,
+                "not_file" => LookupError::NotFile,
+                "not_folder" => LookupError::NotFolder,
+                "restricted_content" => LookupError::RestrictedContent,
+                _ => LookupError::Other(tag),
+            })
+        })
+    }
+}
+
+/// What was in the way when a write failed with a conflict
+#[derive(Debug, PartialEq, Clone)]
+pub enum WriteConflictError {
+    File,
+    Folder,
+    FileAncestor,
+    Other(String),
+}
+
+impl Decodable for WriteConflictError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<WriteConflictError, D::Error> {
+        decoder.read_struct("WriteConflictError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "file" => WriteConflictError::File,
+                "folder" => WriteConflictError::Folder,
+                "file_ancestor" => WriteConflictError::FileAncestor,
+                _ => WriteConflictError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error writing to a path in the user's dropbox
+#[derive(Debug, PartialEq, Clone)]
+pub enum WriteError {
+    MalformedPath(Option<String>),
+    Conflict(WriteConflictError),
+    NoWritePermission,
+    InsufficientSpace,
+    DisallowedName,
+    Other(String),
+}
+
+impl Decodable for WriteError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<WriteError, D::Error> {
+        decoder.read_struct("WriteError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "malformed_path" => WriteError::MalformedPath(try!(decoder.read_struct_field("malformed_path", 0, Decodable::decode))),
+                "conflict" => WriteError::Conflict(try!(decoder.read_struct_field("conflict", 0, Decodable::decode))),
+                "no_write_permission" => WriteError::NoWritePermission,
+                "insufficient_space" => WriteError::InsufficientSpace,
+                "disallowed_name" => WriteError::DisallowedName,
+                _ => WriteError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error returned by `copy_` and `move_`
+#[derive(Debug, PartialEq, Clone)]
+pub enum RelocationError {
+    FromLookup(LookupError),
+    FromWrite(WriteError),
+    To(WriteError),
+    CantCopySharedFolder,
+    CantNestSharedFolder,
+    CantMoveFolderIntoItself,
+    TooManyFiles,
+    Other(String),
+}
+
+impl Decodable for RelocationError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<RelocationError, D::Error> {
+        decoder.read_struct("RelocationError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "from_lookup" => RelocationError::FromLookup(try!(decoder.read_struct_field("from_lookup", 0, Decodable::decode))),
+                "from_write" => RelocationError::FromWrite(try!(decoder.read_struct_field("from_write", 0, Decodable::decode))),
+                "to" => RelocationError::To(try!(decoder.read_struct_field("to", 0, Decodable::decode))),
+                "cant_copy_shared_folder" => RelocationError::CantCopySharedFolder,
+                "cant_nest_shared_folder" => RelocationError::CantNestSharedFolder,
+                "cant_move_folder_into_itself" => RelocationError::CantMoveFolderIntoItself,
+                "too_many_files" => RelocationError::TooManyFiles,
+                _ => RelocationError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error returned by `delete`
+#[derive(Debug, PartialEq, Clone)]
+pub enum DeleteError {
+    PathLookup(LookupError),
+    PathWrite(WriteError),
+    Other(String),
+}
+
+impl Decodable for DeleteError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<DeleteError, D::Error> {
+        decoder.read_struct("DeleteError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "path_lookup" => DeleteError::PathLookup(try!(decoder.read_struct_field("path_lookup", 0, Decodable::decode))),
+                "path_write" => DeleteError::PathWrite(try!(decoder.read_struct_field("path_write", 0, Decodable::decode))),
+                _ => DeleteError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error returned by `create_folder`
+#[derive(Debug, PartialEq, Clone)]
+pub enum CreateFolderError {
+    Path(WriteError),
+    Other(String),
+}
+
+impl Decodable for CreateFolderError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<CreateFolderError, D::Error> {
+        decoder.read_struct("CreateFolderError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "path" => CreateFolderError::Path(try!(decoder.read_struct_field("path", 0, Decodable::decode))),
+                _ => CreateFolderError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error returned by `download`
+#[derive(Debug, PartialEq, Clone)]
+pub enum DownloadError {
+    Path(LookupError),
+    Other(String),
+}
+
+impl Decodable for DownloadError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<DownloadError, D::Error> {
+        decoder.read_struct("DownloadError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "path" => DownloadError::Path(try!(decoder.read_struct_field("path", 0, Decodable::decode))),
+                _ => DownloadError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error returned by `get_metadata`
+#[derive(Debug, PartialEq, Clone)]
+pub enum GetMetadataError {
+    Path(LookupError),
+    Other(String),
+}
+
+impl Decodable for GetMetadataError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<GetMetadataError, D::Error> {
+        decoder.read_struct("GetMetadataError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "path" => GetMetadataError::Path(try!(decoder.read_struct_field("path", 0, Decodable::decode))),
+                _ => GetMetadataError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error returned by `list_folder`
+#[derive(Debug, PartialEq, Clone)]
+pub enum ListFolderError {
+    Path(LookupError),
+    Other(String),
+}
+
+impl Decodable for ListFolderError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<ListFolderError, D::Error> {
+        decoder.read_struct("ListFolderError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "path" => ListFolderError::Path(try!(decoder.read_struct_field("path", 0, Decodable::decode))),
+                _ => ListFolderError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error returned by `list_folder_continue`
+#[derive(Debug, PartialEq, Clone)]
+pub enum ListFolderContinueError {
+    Path(LookupError),
+    /// The cursor is no longer valid, and a new listing has to be started
+    Reset,
+    Other(String),
+}
+
+impl Decodable for ListFolderContinueError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<ListFolderContinueError, D::Error> {
+        decoder.read_struct("ListFolderContinueError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "path" => ListFolderContinueError::Path(try!(decoder.read_struct_field("path", 0, Decodable::decode))),
+                "reset" => ListFolderContinueError::Reset,
+                _ => ListFolderContinueError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error returned by `list_folder_longpoll`
+#[derive(Debug, PartialEq, Clone)]
+pub enum ListFolderLongpollError {
+    /// The cursor is no longer valid, and a new listing has to be started
+    Reset,
+    Other(String),
+}
+
+impl Decodable for ListFolderLongpollError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<ListFolderLongpollError, D::Error> {
+        decoder.read_struct("ListFolderLongpollError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "reset" => ListFolderLongpollError::Reset,
+                _ => ListFolderLongpollError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error returned by `upload`
+#[derive(Debug, PartialEq, Clone)]
+pub enum UploadError {
+    Path {
+        reason: WriteError,
+        upload_session_id: String,
+    },
+    Other(String),
+}
+
+impl Decodable for UploadError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<UploadError, D::Error> {
+        decoder.read_struct("UploadError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "path" => UploadError::Path {
+                    reason: try!(decoder.read_struct_field("reason", 0, Decodable::decode)),
+                    upload_session_id: try!(decoder.read_struct_field("upload_session_id", 0, Decodable::decode)),
+                },
+                _ => UploadError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error finding the upload session referred to by an `UploadSessionCursor`
+#[derive(Debug, PartialEq, Clone)]
+pub enum UploadSessionLookupError {
+    NotFound,
+    /// The offset did not match what dropbox has received so far
+    IncorrectOffset {
+        correct_offset: u64,
+    },
+    Closed,
+    NotClosed,
+    Other(String),
+}
+
+impl Decodable for UploadSessionLookupError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<UploadSessionLookupError, D::Error> {
+        decoder.read_struct("UploadSessionLookupError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "not_found" => UploadSessionLookupError::NotFound,
+                "incorrect_offset" => UploadSessionLookupError::IncorrectOffset {
+                    correct_offset: try!(decoder.read_struct_field("correct_offset", 0, Decodable::decode)),
+                },
+                "closed" => UploadSessionLookupError::Closed,
+                "not_closed" => UploadSessionLookupError::NotClosed,
+                _ => UploadSessionLookupError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error committing an upload session
+#[derive(Debug, PartialEq, Clone)]
+pub enum UploadSessionFinishError {
+    LookupFailed(UploadSessionLookupError),
+    Path(WriteError),
+    TooManySharedFolderTargets,
+    TooManyWriteOperations,
+    Other(String),
+}
+
+impl Decodable for UploadSessionFinishError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<UploadSessionFinishError, D::Error> {
+        decoder.read_struct("UploadSessionFinishError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "lookup_failed" => UploadSessionFinishError::LookupFailed(try!(decoder.read_struct_field("lookup_failed", 0, Decodable::decode))),
+                "path" => UploadSessionFinishError::Path(try!(decoder.read_struct_field("path", 0, Decodable::decode))),
+                "too_many_shared_folder_targets" => UploadSessionFinishError::TooManySharedFolderTargets,
+                "too_many_write_operations" => UploadSessionFinishError::TooManyWriteOperations,
+                _ => UploadSessionFinishError::Other(tag),
+            })
+        })
+    }
+}
+
+/// Error checking on the status of an asynchronous job
+#[derive(Debug, PartialEq, Clone)]
+pub enum PollError {
+    InvalidAsyncJobId,
+    InternalError,
+    Other(String),
+}
+
+impl Decodable for PollError {
+    fn decode<D: Decoder>(decoder: &mut D) -> ::std::result::Result<PollError, D::Error> {
+        decoder.read_struct("PollError", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            Ok(match &tag[..] {
+                "invalid_async_job_id" => PollError::InvalidAsyncJobId,
+                "internal_error" => PollError::InternalError,
+                _ => PollError::Other(tag),
+            })
+        })
+    }
+}
+
+// Functions
+
+/// Copy a file
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files;
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let metadata = try!(files::copy_(&client, "/Path/to/existing/file", "/Path/to/new/file"));
+/// ```
+pub fn copy_<T>(client: &T, from: &str, to: &str) -> Result<Metadata>
+                where T: DropboxClient
+{
+    let mut map = BTreeMap::new();
+    map.insert("from_path".to_string(), json::Json::String(from.to_string()));
+    map.insert("to_path".to_string(), json::Json::String(to.to_string()));
+    let mut headers = BTreeMap::new();
+    let resp = try!(client.api("files/copy", &mut headers, Some(&map))
+                        .map_err(|e| route_error(e, ApiError::RelocationError)));
+    json::decode(&try!(resp.text())).map_err(|e| ApiError::from(e))
+}
+
+/// Create a folder
+///
+/// # Example
 ///
 /// ```ignore
 /// use std::env;
@@ -206,8 +1316,9 @@ pub fn create_folder<T>(client: &T, path: &str) -> Result<NewFolder>
     let mut map = BTreeMap::new();
     map.insert("path".to_string(), json::Json::String(path.to_string()));
     let mut headers = BTreeMap::new();
-    let resp = try!(client.api("files/create_folder", &mut headers, Some(map)));
-    json::decode(&resp.body).map_err(|e| ApiError::from(e))
+    let resp = try!(client.api("files/create_folder", &mut headers, Some(map))
+                        .map_err(|e| route_error(e, ApiError::CreateFolderError)));
+    json::decode(&try!(resp.text())).map_err(|e| ApiError::from(e))
 }
 
 /// Delete a file or folder from the user's dropbox acconut
@@ -222,17 +1333,21 @@ pub fn create_folder<T>(client: &T, path: &str) -> Result<NewFolder>
 /// let client = Client::new(env::var("DROPBOX_TOKEN"));
 /// let deleted = files::delete(&client, "/path/to/file/or/folder");
 /// ```
-/// TODO error handling
 pub fn delete<T: DropboxClient>(client: &T, path: &str) -> Result<Metadata> {
     let mut map = BTreeMap::new();
     map.insert("path".to_string(), json::Json::String(path.to_string()));
     let mut headers = BTreeMap::new();
-    let resp = try!(client.api("files/delete", &mut headers, Some(map)));
-    json::decode(&resp.body).map_err(|e| ApiError::from(e))
+    let resp = try!(client.api("files/delete", &mut headers, Some(map))
+                        .map_err(|e| route_error(e, ApiError::DeleteError)));
+    json::decode(&try!(resp.text())).map_err(|e| ApiError::from(e))
 }
 
 /// Download a file
 ///
+/// The body of the returned `Response` is read straight from the connection. To follow its
+/// progress, cancel it part way or check it against the file's `content_hash`, use
+/// `download_to` instead.
+///
 /// # Example
 ///
 /// ```ignore
@@ -245,12 +1360,59 @@ pub fn delete<T: DropboxClient>(client: &T, path: &str) -> Result<Metadata> {
 /// let (metadata, response) = try!(files::download(&client, "/Path/to/file"));
 /// ```
 pub fn download<T: DropboxClient>(client: &T, path: &str) -> Result<(FileMetadata, Response)> {
+    download_with_headers(client, path, BTreeMap::new())
+}
+
+/// Download part of a file
+///
+/// Fetches the bytes from `start` up to and including `end`, or up to the end of the file if
+/// `end` is `None`. If `rev` is given, the download fails with `ApiError::RevisionChanged` when
+/// the file is no longer at that revision, so a file that is fetched in several ranges can't end
+/// up stitched together from different versions.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files;
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let (metadata, first) = try!(files::download_range(&client, "/path/to/file", 0, Some(1023), None));
+/// let (_, rest) = try!(files::download_range(&client, "/path/to/file", 1024, None, Some(metadata.rev())));
+/// ```
+pub fn download_range<T>(client: &T, path: &str, start: u64, end: Option<u64>, rev: Option<&str>) -> Result<(FileMetadata, Response)>
+                where T: DropboxClient
+{
+    let range = match end {
+        Some(end) => format!("bytes={}-{}", start, end),
+        None => format!("bytes={}-", start),
+    };
+    let mut headers = BTreeMap::new();
+    headers.insert("Range".to_string(), range);
+    let (metadata, resp) = try!(download_with_headers(client, path, headers));
+    if let Some(rev) = rev {
+        if metadata.rev() != rev {
+            return Err(ApiError::RevisionChanged(rev.to_owned(), metadata.rev().to_owned()));
+        }
+    }
+    // A 200 carries the whole file, which is only what was asked for when that is the range
+    let whole_file = start == 0 && end.is_none();
+    if resp.status != 206 && !whole_file {
+        return Err(ApiError::HttpError("Range request was answered with the whole file".to_owned()));
+    }
+    Ok((metadata, resp))
+}
+
+fn download_with_headers<T>(client: &T, path: &str, mut headers: BTreeMap<String, String>) -> Result<(FileMetadata, Response)>
+                where T: DropboxClient
+{
     let mut map = BTreeMap::new();
     map.insert("path".to_string(), json::Json::String(path.to_string()));
-    let mut headers = BTreeMap::new();
-    headers.insert("Dropbox-API-Arg".to_string(), json::encode(&map).unwrap());
+    headers.insert("Dropbox-API-Arg".to_string(), api_arg(&map));
     headers.insert("Content-Type".to_string(), "".to_string());
-    let resp = try!(client.content("files/download", &mut headers, None::<&str>));
+    let resp = try!(client.content("files/download", &mut headers, Body::Empty)
+                        .map_err(|e| route_error(e, ApiError::DownloadError)));
     let metadata: FileMetadata = match resp.api_result {
         Some(ref data) => {
             try!(json::decode(data))
@@ -263,35 +1425,231 @@ pub fn download<T: DropboxClient>(client: &T, path: &str) -> Result<(FileMetadat
     ))
 }
 
-/// TODO implement
-pub fn download_to_file<T>(client: &T, dest_path: &str, path: &str) -> Result<(Metadata, Response)>
+/// Download a file to the local filesystem
+///
+/// The file is first written to a hidden temporary file next to `dest_path`, which is synced to
+/// disk and then renamed over `dest_path`, so `dest_path` never holds a partial download. On
+/// unix the directory is synced after the rename as well. The local modification time is set to
+/// the file's `client_modified` time, unless that is before 1970.
+///
+/// If the download is interrupted, the temporary file is kept, and the next call for the same
+/// revision of the file picks up where it left off. Temporary files for other revisions are
+/// removed. Returns the file's metadata and its size.
+///
+/// Progress is reported to `transfer`. A cancelled download keeps its temporary file, so it can
+/// be resumed later.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files::{self, Transfer};
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let transfer = Transfer::new().on_progress(|done, total| println!("{}/{:?}", done, total));
+/// let (metadata, size) = try!(files::download_to_file(&client, "/tmp/file", "/path/to/file", &transfer));
+/// ```
+pub fn download_to_file<T>(client: &T, dest_path: &str, path: &str, transfer: &Transfer) -> Result<(FileMetadata, u64)>
                 where T: DropboxClient
 {
-    Ok((
-        Default::default(),
-        Response {
-            status: 200,
-            api_result: None,
-            body: "".to_string(),
+    try!(transfer.check());
+    let metadata = match try!(get_metadata(client, path, false)) {
+        Metadata::File(metadata) => metadata,
+        _ => return Err(ApiError::NotFile(path.to_owned())),
+    };
+    let dest_path = Path::new(dest_path);
+    let partial_path = try!(partial_path(dest_path, metadata.rev()));
+    try!(remove_stale_partials(dest_path, &partial_path));
+    match write_partial(client, &partial_path, path, &metadata, transfer) {
+        Ok(size) => {
+            try!(fs::rename(&partial_path, dest_path));
+            try!(sync_parent(dest_path));
+            Ok((metadata, size))
         },
-    ))
+        Err(e) => {
+            match e {
+                ApiError::RevisionChanged(..) | ApiError::ContentHashMismatch(..) => {
+                    fs::remove_file(&partial_path);
+                },
+                _ => {},
+            }
+            Err(e)
+        },
+    }
+}
+
+/// Syncs the directory holding `path`, so a rename into it survives a crash
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    try!(try!(File::open(parent)).sync_all());
+    Ok(())
+}
+
+/// Directories can't be opened for syncing here, so the rename is left to the filesystem
+#[cfg(not(unix))]
+fn sync_parent(path: &Path) -> Result<()> {
+    Ok(())
+}
+
+/// Where `download_to_file` keeps revision `rev` of the download for `dest_path` until it is complete
+fn partial_path(dest_path: &Path, rev: &str) -> Result<PathBuf> {
+    match dest_path.file_name() {
+        Some(name) => Ok(dest_path.with_file_name(format!(".{}.{}.dbox-part", name.to_string_lossy(), rev))),
+        None => Err(ApiError::IoError(io::ErrorKind::InvalidInput, format!("Not a file path: {}", dest_path.display()))),
+    }
+}
+
+/// Removes partial downloads for `dest_path` that were left behind by older revisions of the file
+fn remove_stale_partials(dest_path: &Path, partial_path: &Path) -> Result<()> {
+    let (dir, name) = match (dest_path.parent(), dest_path.file_name()) {
+        (Some(dir), Some(name)) => (if dir == Path::new("") { Path::new(".") } else { dir }, name.to_string_lossy()),
+        _ => return Ok(()),
+    };
+    let prefix = format!(".{}.", name);
+    for entry in try!(fs::read_dir(dir)) {
+        let entry = try!(entry);
+        let file_name = entry.file_name();
+        let file_name = file_name.to_string_lossy();
+        if !file_name.starts_with(&prefix[..]) || entry.path() == partial_path {
+            continue;
+        }
+        // Revs never contain a '.', which keeps the partials of `name.ext` apart from `name`'s
+        let is_partial = match file_name[prefix.len()..].find('.') {
+            Some(i) => i > 0 && &file_name[prefix.len() + i..] == ".dbox-part",
+            None => false,
+        };
+        if is_partial {
+            try!(fs::remove_file(entry.path()));
+        }
+    }
+    Ok(())
+}
+
+/// Fills in the partial file for `metadata`, resuming from however much of it is already there
+fn write_partial<T>(client: &T, partial_path: &Path, path: &str, metadata: &FileMetadata, transfer: &Transfer) -> Result<u64>
+                where T: DropboxClient
+{
+    let mut file = try!(OpenOptions::new().create(true).append(true).open(partial_path));
+    let size = metadata.size();
+    let mut offset = try!(file.metadata()).len();
+    if offset > size {
+        try!(file.set_len(0));
+        offset = 0;
+    }
+    let mut attempts = 0;
+    while offset < size {
+        attempts += 1;
+        let result = download_range(client, path, offset, None, Some(metadata.rev())).and_then(|(_, mut resp)| {
+            copy_with_transfer(&mut resp, &mut file, transfer, offset, Some(size), None)
+        });
+        offset = try!(file.metadata()).len();
+        match result {
+            // The connection dropped mid-download, so carry on from what made it to disk
+            Ok(_) | Err(ApiError::IoError(..)) if attempts < DOWNLOAD_ATTEMPTS => {},
+            Ok(_) if offset >= size => {},
+            Ok(_) => return Err(ApiError::IoError(io::ErrorKind::UnexpectedEof, "Download ended early".to_owned())),
+            Err(e) => return Err(e),
+        }
+    }
+    try!(file.sync_all());
+    drop(file);
+    if transfer.verify {
+        // A resumed download was written over several requests, so the whole file is hashed
+        let hash = try!(content_hash::hash_reader(&mut try!(File::open(partial_path))));
+        try!(check_content_hash(metadata, &hash));
+    }
+
+    // A timestamp from before 1970 can't be set, so the file keeps its own
+    let modified = metadata.client_modified().timestamp();
+    if modified >= 0 {
+        let accessed = FileTime::from_last_access_time(&try!(fs::metadata(partial_path)));
+        let modified = FileTime::from_seconds_since_1970(modified as u64, 0);
+        try!(filetime::set_file_times(partial_path, accessed, modified));
+    }
+    Ok(size)
 }
 
-pub fn download_to<T, U>(client: &T, path: &str, writer: U) -> Result<(Metadata, Response)>
+/// Download a file into a writer
+///
+/// The contents are copied into `writer` as they arrive, so the file is never held in memory as
+/// a whole, and progress is reported to `transfer`. Returns the file's metadata and the number
+/// of bytes written.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use std::io;
+/// use dbox::client::Client;
+/// use dbox::files::{self, Transfer};
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let stdout = io::stdout();
+/// let (metadata, written) = try!(files::download_to(&client, "/path/to/file", stdout.lock(), &Transfer::new()));
+/// ```
+pub fn download_to<T, U>(client: &T, path: &str, mut writer: U, transfer: &Transfer) -> Result<(FileMetadata, u64)>
                 where T: DropboxClient,
                       U: Write
 {
-    Ok((
-            Default::default(),
-            Response {
-                status: 200,
-                api_result: None,
-                body: "".to_string(),
-            }
-    ))
+    try!(transfer.check());
+    let (metadata, mut resp) = try!(download(client, path));
+    let size = metadata.size();
+    if !transfer.verify {
+        let written = try!(copy_with_transfer(&mut resp, &mut writer, transfer, 0, Some(size), None));
+        return Ok((metadata, written));
+    }
+    let mut hasher = ContentHasher::new();
+    let written = try!(copy_with_transfer(&mut resp, &mut writer, transfer, 0, Some(size), Some(&mut hasher)));
+    try!(check_content_hash(&metadata, &hasher.finish()));
+    Ok((metadata, written))
 }
 
-/// Get metadata for a file
+fn check_content_hash(metadata: &FileMetadata, actual: &str) -> Result<()> {
+    match metadata.content_hash() {
+        Some(expected) if expected != actual => {
+            Err(ApiError::ContentHashMismatch(expected.to_owned(), actual.to_owned()))
+        },
+        _ => Ok(()),
+    }
+}
+
+/// Copies `reader` into `writer` like `io::copy`, checking for cancellation and reporting
+/// progress after every buffer
+///
+/// `done` is how much of the transfer was already finished before the copy started. Everything
+/// copied is also fed to `hasher`, if there is one.
+fn copy_with_transfer<R, W>(reader: &mut R, writer: &mut W, transfer: &Transfer, done: u64, total: Option<u64>,
+                            mut hasher: Option<&mut ContentHasher>) -> Result<u64>
+                where R: Read,
+                      W: Write
+{
+    let mut buf = vec![0; COPY_BUFFER_SIZE];
+    let mut written = 0;
+    loop {
+        try!(transfer.check());
+        let len = match reader.read(&mut buf) {
+            Ok(0) => return Ok(written),
+            Ok(len) => len,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(ApiError::from(e)),
+        };
+        try!(writer.write_all(&buf[..len]));
+        if let Some(ref mut hasher) = hasher {
+            hasher.update(&buf[..len]);
+        }
+        written += len as u64;
+        transfer.report(done + written, total);
+    }
+}
+
+/// Get metadata for a file or folder
+///
+/// With `include_media_info` set, photos and videos come back with their `MediaInfo`.
 ///
 /// # Example
 ///
@@ -303,45 +1661,34 @@ pub fn download_to<T, U>(client: &T, path: &str, writer: U) -> Result<(Metadata,
 /// let client = Client::new(env::var("DROPBOX_TOKEN"));
 /// let metadata = try!(files::get_metadata(&client, "/path/to/file", false));
 /// ```
-///
-/// TODO error handling
-pub fn get_metadata<T>(client: &T, path: &str, include_media_info: bool) -> Result<FileMetadata>
+pub fn get_metadata<T>(client: &T, path: &str, include_media_info: bool) -> Result<Metadata>
                 where T: DropboxClient
 {
     let mut map = BTreeMap::new();
     map.insert("path", json::Json::String(path.to_string()));
     map.insert("include_media_info", json::Json::Boolean(include_media_info));
     let mut headers = BTreeMap::new();
-    let resp = try!(client.api("files/get_metadata", &mut headers, Some(map)));
-    json::decode(&resp.body).map_err(ApiError::from)
+    let resp = try!(client.api("files/get_metadata", &mut headers, Some(map))
+                        .map_err(|e| route_error(e, ApiError::GetMetadataError)));
+    json::decode(&try!(resp.text())).map_err(ApiError::from)
 }
 
 /// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn get_preview<T>(client: &T, path: &str) -> Result<(Metadata, Response)>
                 where T: DropboxClient
 {
-    Ok((
-        Default::default(),
-        Response {
-            status: 200,
-            api_result: None,
-            body: "".to_string(),
-        },
-    ))
+    Err(ApiError::Unimplemented("files/get_preview".to_owned()))
 }
 
 /// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn get_preview_to_file<T>(client: &T, dest_path: &str, path: &str) -> Result<(Metadata, Response)>
                 where T: DropboxClient
 {
-    Ok((
-        Default::default(),
-        Response {
-            status: 200,
-            api_result: None,
-            body: "".to_string(),
-        },
-    ))
+    Err(ApiError::Unimplemented("files/get_preview".to_owned()))
 }
 
 pub fn get_thumbnail<T>(client: &T, path: &str) -> Result<(Metadata, Response)>
@@ -351,17 +1698,12 @@ pub fn get_thumbnail<T>(client: &T, path: &str) -> Result<(Metadata, Response)>
 }
 
 /// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn get_thumbnail_with_options<T>(client: &T, path: &str, options: ThumbnailOptions) -> Result<(Metadata, Response)>
                 where T: DropboxClient
 {
-    Ok((
-        Default::default(),
-        Response {
-            status: 200,
-            api_result: None,
-            body: "".to_string(),
-        },
-    ))
+    Err(ApiError::Unimplemented("files/get_thumbnail".to_owned()))
 }
 
 pub fn get_thumbnail_to_file<T>(client: &T, dest_path: &str, path: &str) -> Result<(Metadata, Response)>
@@ -371,17 +1713,12 @@ pub fn get_thumbnail_to_file<T>(client: &T, dest_path: &str, path: &str) -> Resu
 }
 
 /// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn get_thumbnail_to_file_with_options<T>(client: &T, dest_path: &str, path: &str, options: ThumbnailOptions) -> Result<(Metadata, Response)>
                 where T: DropboxClient
 {
-    Ok((
-        Default::default(),
-        Response {
-            status: 200,
-            api_result: None,
-            body: "".to_string(),
-        },
-    ))
+    Err(ApiError::Unimplemented("files/get_thumbnail".to_owned()))
 }
 
 /// List the entries in a user's dropbox folder
@@ -396,47 +1733,186 @@ pub fn get_thumbnail_to_file_with_options<T>(client: &T, dest_path: &str, path:
 /// let client = Client::new(env::var("DROPBOX_TOKEN"));
 /// let folderlist = files::list_folder(&client, "/path/to/folder");
 /// ```
-/// TODO error handling
 pub fn list_folder<T: DropboxClient>(client: &T, path: &str) -> Result<FolderList> {
-    let mut map = BTreeMap::new();
-    map.insert("path".to_string(), json::Json::String(path.to_string()));
-    map.insert("recursive".to_string(), json::Json::Boolean(false));
-    map.insert("include_media_info".to_string(), json::Json::Boolean(false));
-    map.insert("include_deleted".to_string(), json::Json::Boolean(false));
-    let mut headers = BTreeMap::new();
-    let resp = try!(client.api("files/list_folder", &mut headers, Some(&map)));
-    json::decode(&resp.body).map_err(ApiError::from)
+    list_folder_with_options(client, path, Default::default())
 }
 
-/// TODO implement
-pub fn list_folder_continue<T>(client: &T, cursor: &str) -> Result<FolderList>
+/// List the entries in a user's dropbox folder, with options
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files::{self, ListFolderOptions};
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let options = ListFolderOptions::new().recursive(true);
+/// let folderlist = try!(files::list_folder_with_options(&client, "/path/to/folder", options));
+/// ```
+pub fn list_folder_with_options<T>(client: &T, path: &str, options: ListFolderOptions) -> Result<FolderList>
+                where T: DropboxClient
+{
+    let map = options.args(path);
+    let mut headers = BTreeMap::new();
+    let resp = try!(client.api("files/list_folder", &mut headers, Some(&map))
+                        .map_err(|e| route_error(e, ApiError::ListFolderError)));
+    json::decode(&try!(resp.text())).map_err(ApiError::from)
+}
+
+/// Iterate over every entry in a folder, fetching further pages as needed
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files;
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let mut entries = files::list_folder_iter(&client, "/path/to/folder");
+/// for entry in &mut entries {
+///     let metadata = try!(entry);
+/// }
+/// let cursor = entries.cursor();
+/// ```
+pub fn list_folder_iter<'a, T>(client: &'a T, path: &str) -> ListFolderIter<'a, T>
+                where T: DropboxClient
+{
+    list_folder_iter_with_options(client, path, Default::default())
+}
+
+/// Iterate over every entry in a folder, with options
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files::{self, ListFolderOptions};
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let options = ListFolderOptions::new().recursive(true);
+/// for entry in files::list_folder_iter_with_options(&client, "", options) {
+///     let metadata = try!(entry);
+/// }
+/// ```
+pub fn list_folder_iter_with_options<'a, T>(client: &'a T, path: &str, options: ListFolderOptions) -> ListFolderIter<'a, T>
                 where T: DropboxClient
 {
-    Ok(Default::default())
+    ListFolderIter {
+        client: client,
+        path: path.to_owned(),
+        options: options,
+        entries: vec![].into_iter(),
+        cursor: None,
+        has_more: true,
+    }
 }
 
+/// Get the next page of a folder listing, or the changes since the cursor was returned
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files;
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let folderlist = try!(files::list_folder(&client, "/path/to/folder"));
+/// let next = try!(files::list_folder_continue(&client, folderlist.cursor()));
+/// ```
+pub fn list_folder_continue<T>(client: &T, cursor: &str) -> Result<FolderList>
+                where T: DropboxClient
+{
+    let mut map = BTreeMap::new();
+    map.insert("cursor".to_string(), json::Json::String(cursor.to_string()));
+    let mut headers = BTreeMap::new();
+    let resp = try!(client.api("files/list_folder/continue", &mut headers, Some(&map))
+                        .map_err(|e| route_error(e, ApiError::ListFolderContinueError)));
+    json::decode(&try!(resp.text())).map_err(ApiError::from)
+}
 
+/// Get a cursor for the current state of a folder, without listing it
+///
+/// Passing the cursor to `list_folder_continue` or `list_folder_longpoll` later on gives the
+/// changes made since this call.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files;
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let cursor = try!(files::list_folder_get_latest_cursor(&client, "/path/to/folder"));
+/// ```
 pub fn list_folder_get_latest_cursor<T>(client: &T, path: &str) -> Result<String>
                 where T: DropboxClient
 {
     list_folder_get_latest_cursor_with_options(client, path, Default::default())
 }
 
-/// TODO implement
+/// Get a cursor for the current state of a folder, with options
+///
+/// The options have to match the ones the folder will be listed with, e.g. a recursive cursor
+/// also gives changes in subfolders.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files::{self, GetCursorOptions};
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let options = GetCursorOptions::new().recursive(true);
+/// let cursor = try!(files::list_folder_get_latest_cursor_with_options(&client, "/path/to/folder", options));
+/// ```
 pub fn list_folder_get_latest_cursor_with_options<T>(client: &T, path: &str, options: GetCursorOptions) -> Result<String>
                 where T: DropboxClient
 {
-    Ok("".to_string())
+    let map = options.args(path);
+    let mut headers = BTreeMap::new();
+    let resp = try!(client.api("files/list_folder/get_latest_cursor", &mut headers, Some(&map))
+                        .map_err(|e| route_error(e, ApiError::ListFolderError)));
+    let result: GetLatestCursorResult = try!(json::decode(&try!(resp.text())));
+    Ok(result.cursor)
 }
 
-/// TODO implement
+/// Wait for changes to a folder since `cursor` was returned, for up to `timeout` seconds
+///
+/// Dropbox only accepts timeouts between 30 and 480 seconds, so anything outside that range is
+/// clamped to it. This goes to the notify endpoint, which doesn't need the access token. See
+/// `Watcher` for a loop around it that honors the `backoff` dropbox asks for.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files;
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let cursor = try!(files::list_folder_get_latest_cursor(&client, "/path/to/folder"));
+/// let result = try!(files::list_folder_longpoll(&client, &cursor, 60));
+/// if result.changes() {
+///     let changes = try!(files::list_folder_continue(&client, &cursor));
+/// }
+/// ```
 pub fn list_folder_longpoll<T>(client: &T, cursor: &str, timeout: usize) -> Result<FolderListLongpoll>
                 where T: DropboxClient
 {
-    Ok(FolderListLongpoll {
-        changes: false,
-        backoff: None,
-    })
+    let mut map = BTreeMap::new();
+    map.insert("cursor".to_string(), json::Json::String(cursor.to_string()));
+    let timeout = cmp::max(cmp::min(timeout, LONGPOLL_MAX_TIMEOUT), LONGPOLL_TIMEOUT);
+    map.insert("timeout".to_string(), json::Json::U64(timeout as u64));
+    let mut headers = BTreeMap::new();
+    let resp = try!(client.notify("files/list_folder/longpoll", &mut headers, Some(&map))
+                        .map_err(|e| route_error(e, ApiError::ListFolderLongpollError)));
+    json::decode(&try!(resp.text())).map_err(ApiError::from)
 }
 
 /// TODO implement
@@ -462,8 +1938,6 @@ pub fn list_revisions<T>(client: &T, path: &str, limit: usize) -> Result<ListRev
 /// let client = Client::new(env::var("DROPBOX_TOKEN"));
 /// let metadata = try!(files::move(&client, "/path/to/existing/file", "/path/to/new/location/for/file"));
 /// ```
-///
-/// TODO error handling
 pub fn move_<T>(client: &T, from: &str, to: &str) -> Result<Metadata>
                 where T: DropboxClient
 {
@@ -471,8 +1945,9 @@ pub fn move_<T>(client: &T, from: &str, to: &str) -> Result<Metadata>
     map.insert("from_path", json::Json::String(from.to_string()));
     map.insert("to_path", json::Json::String(to.to_string()));
     let mut headers = BTreeMap::new();
-    let resp = try!(client.api("files/move", &mut headers, Some(map)));
-    json::decode(&resp.body).map_err(ApiError::from)
+    let resp = try!(client.api("files/move", &mut headers, Some(map))
+                        .map_err(|e| route_error(e, ApiError::RelocationError)));
+    json::decode(&try!(resp.text())).map_err(ApiError::from)
 }
 
 /// TODO implement
@@ -483,10 +1958,12 @@ pub fn permanently_delete<T>(client: &T, path: &str) -> Result<()>
 }
 
 /// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn restore<T>(client: &T, path: &str, rev: &str) -> Result<Metadata>
                 where T: DropboxClient
 {
-    Ok(Default::default())
+    Err(ApiError::Unimplemented("files/restore".to_owned()))
 }
 
 pub fn search<T>(client: &T, path: &str, query: &str) -> Result<Search>
@@ -516,65 +1993,1056 @@ pub fn search_with_options<T>(client: &T, path: &str, query: &str, options: Sear
 /// use dbox::files;
 ///
 /// let client = Client::new(env::var("DROPBOX_TOKEN"));
-/// let metadata = try!(files::upload(&client, "file contents", "/path/to/file"));
+/// let metadata = try!(files::upload(&client, b"file contents", "/path/to/file"));
 /// ```
-///
-/// TODO error handling
-pub fn upload<T>(client: &T, contents: &str, path: &str) -> Result<FileMetadata>
+pub fn upload<T>(client: &T, contents: &[u8], path: &str) -> Result<FileMetadata>
                 where T: DropboxClient
 {
     upload_with_options(client, contents, path, Default::default())
 }
 
 /// Upload a file to the user's dropbox acconut
-/// 
+///
+/// The contents go out in a single request, so the only progress reported to `options.transfer`
+/// is the whole file, once dropbox has it. A transfer that is already cancelled fails before
+/// anything is sent.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files;
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let upload_options = UploadOptions { mode: WriteMode::Overwrite, autorename: true, .. Default::default() };
+/// let metadata = try!(files::upload_with_options(&client, b"file contents", "/path/to/file", upload_options));
+/// ```
+pub fn upload_with_options<T>(client: &T, contents: &[u8], path: &str, options: UploadOptions) -> Result<FileMetadata>
+                where T: DropboxClient
+{
+    try!(options.transfer.check());
+    let mut headers = upload_headers(path, &options);
+    let resp = try!(client.content("files/upload", &mut headers, Body::Bytes(contents))
+                        .map_err(|e| route_error(e, ApiError::UploadError)));
+    let metadata = try!(json::decode(&try!(resp.text())));
+    options.transfer.report(contents.len() as u64, Some(contents.len() as u64));
+    Ok(metadata)
+}
+
+/// Upload a file, streaming its contents from a reader
+///
+/// The contents are sent in a single request, so this is limited to files of up to 150MB.
+/// Unlike `upload_with_options`, the request is not retried if it fails. Progress is reported to
+/// `options.transfer` once the request is done.
+///
 /// # Example
 ///
 /// ```ignore
 /// use std::env;
+/// use std::fs::File;
 /// use dbox::client::Client;
 /// use dbox::files;
 ///
 /// let client = Client::new(env::var("DROPBOX_TOKEN"));
-/// let upload_options = UploadOptions { mode: WriteMode::Overwrite, autorename: true, mute: false };
-/// let metadata = try!(files::upload_with_options(&client, "file contents", "/path/to/file", upload_options));
+/// let mut file = try!(File::open("photo.jpg"));
+/// let metadata = try!(files::upload_stream(&client, &mut file, "/path/to/photo.jpg", Default::default()));
 /// ```
+pub fn upload_stream<T, R>(client: &T, reader: &mut R, path: &str, options: UploadOptions) -> Result<FileMetadata>
+                where T: DropboxClient,
+                      R: Read
+{
+    try!(options.transfer.check());
+    let mut headers = upload_headers(path, &options);
+    let mut reader = CountingReader { inner: reader, count: 0 };
+    let resp = try!(client.content("files/upload", &mut headers, Body::Reader(&mut reader))
+                        .map_err(|e| route_error(e, ApiError::UploadError)));
+    let metadata = try!(json::decode(&try!(resp.text())));
+    options.transfer.report(reader.count, Some(reader.count));
+    Ok(metadata)
+}
+
+/// Upload a local file
+///
+/// See `upload_reader` for how the file is uploaded. The size of the file is known up front, so
+/// it is reported to `options.transfer` as the total.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files::{self, Transfer, UploadOptions};
 ///
-/// TODO error handling
-pub fn upload_with_options<T>(client: &T, contents: &str, path: &str, options: UploadOptions) -> Result<FileMetadata>
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let transfer = Transfer::new().on_progress(|done, total| println!("{}/{:?}", done, total));
+/// let options = UploadOptions::default().transfer(transfer);
+/// let metadata = try!(files::upload_file(&client, "backup.tar.gz", "/backups/backup.tar.gz", options));
+/// ```
+pub fn upload_file<T>(client: &T, local_path: &str, path: &str, options: UploadOptions) -> Result<FileMetadata>
                 where T: DropboxClient
 {
-    let mut map = BTreeMap::new();
-    map.insert("path", json::Json::String(path.to_string()));
-    map.insert("mode", json::Json::String(format!("{}", options.mode)));
-    map.insert("autorename", json::Json::Boolean(options.autorename));
-    map.insert("mute", json::Json::Boolean(options.mute));
+    let file = try!(File::open(local_path));
+    let size = try!(file.metadata()).len();
+    if options.skip_unchanged {
+        let hash = try!(content_hash::hash_file(local_path));
+        if let Ok(Metadata::File(metadata)) = get_metadata(client, path, false) {
+            if metadata.content_hash() == Some(&hash[..]) {
+                options.transfer.report(size, Some(size));
+                return Ok(metadata);
+            }
+        }
+    }
+    upload_sized(client, file, Some(size), path, options)
+}
+
+/// Upload a file of any size, reading its contents from `reader`
+///
+/// Contents of up to `options.upload_threshold` bytes are sent in a single request. Anything
+/// larger is sent through an upload session in chunks of `options.chunk_size`, so at most one
+/// chunk is held in memory at a time. A chunk that fails is sent again, starting from wherever
+/// dropbox says the session is up to.
+///
+/// Progress is reported to `options.transfer` once the single request is done for small files,
+/// and after each chunk for files sent through an upload session. The total size isn't known up
+/// front.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use std::io;
+/// use dbox::client::Client;
+/// use dbox::files;
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let stdin = io::stdin();
+/// let metadata = try!(files::upload_reader(&client, stdin.lock(), "/path/to/file", Default::default()));
+/// ```
+pub fn upload_reader<T, R>(client: &T, reader: R, path: &str, options: UploadOptions) -> Result<FileMetadata>
+                where T: DropboxClient,
+                      R: Read
+{
+    upload_sized(client, reader, None, path, options)
+}
+
+/// Does the work of `upload_reader`, where `size` is the total to report, if known
+fn upload_sized<T, R>(client: &T, reader: R, size: Option<u64>, path: &str, options: UploadOptions) -> Result<FileMetadata>
+                where T: DropboxClient,
+                      R: Read
+{
+    let mut reader = reader;
+    let transfer = options.transfer.clone();
+    try!(transfer.check());
+    // The fields can be set directly, so they are clamped here as well as in the builders
+    let threshold = cmp::min(options.upload_threshold, UPLOAD_LIMIT);
+    let head = try!(read_chunk(&mut reader, threshold.saturating_add(1)));
+    if head.len() <= threshold {
+        return upload_with_options(client, &head, path, options);
+    }
+
+    let session_id = try!(session_start(client, Body::Empty, false, false));
+    let mut cursor = UploadSessionCursor::new(&session_id, 0);
+    let mut reader = io::Cursor::new(head).chain(reader);
+    let chunk_size = cmp::max(cmp::min(options.chunk_size, UPLOAD_LIMIT), 1);
+    loop {
+        try!(transfer.check());
+        let chunk = try!(read_chunk(&mut reader, chunk_size));
+        if chunk.is_empty() {
+            break;
+        }
+        try!(append_chunk(client, &mut cursor, &chunk, false, false));
+        transfer.report(cursor.offset, size);
+    }
+    session_finish(client, Body::Empty, &cursor, &commit_info(path, &options))
+}
+
+/// Upload a file of any size, sending several chunks of it at the same time
+///
+/// Works like `upload_reader`, except that the chunks of a large file are appended to a
+/// concurrent upload session by up to `client.connection_limit()` threads at once. The chunk
+/// size is rounded down to a multiple of 4MB, as dropbox requires for concurrent sessions. Up to
+/// `client.connection_limit() + 2` chunks are held in memory at once: one per thread, plus the
+/// chunk being read and the last chunk, which is held back to close the session.
+///
+/// Progress is reported to `options.transfer` as each chunk completes, which may not be in
+/// order. Once cancelled, no more chunks are started, but the ones already in flight are allowed
+/// to finish.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use std::fs::File;
+/// use dbox::client::Client;
+/// use dbox::files;
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN")).max_connections(4);
+/// let file = try!(File::open("backup.tar.gz"));
+/// let metadata = try!(files::upload_parallel(&client, file, "/backups/backup.tar.gz", Default::default()));
+/// ```
+pub fn upload_parallel<T, R>(client: &T, reader: R, path: &str, options: UploadOptions) -> Result<FileMetadata>
+                where T: DropboxClient + Clone + Send + 'static,
+                      R: Read
+{
+    let mut reader = reader;
+    let transfer = options.transfer.clone();
+    try!(transfer.check());
+    let chunk_size = cmp::max(cmp::min(options.chunk_size, UPLOAD_LIMIT) / CONCURRENT_CHUNK_ALIGN, 1) * CONCURRENT_CHUNK_ALIGN;
+    let mut chunk = try!(read_chunk(&mut reader, chunk_size));
+    let mut next = try!(read_chunk(&mut reader, chunk_size));
+    if next.is_empty() && chunk.len() <= cmp::min(options.upload_threshold, UPLOAD_LIMIT) {
+        return upload_with_options(client, &chunk, path, options);
+    }
+
+    let session_id = try!(session_start(client, Body::Empty, false, true));
+    // A rendezvous channel, so a chunk is only read once a worker is free to send it
+    let (jobs, jobs_rx) = mpsc::sync_channel::<(u64, Vec<u8>)>(0);
+    let jobs_rx = Arc::new(Mutex::new(jobs_rx));
+    let (results_tx, results) = mpsc::channel();
+    let workers = (0..cmp::max(client.connection_limit(), 1)).map(|_| {
+        let client = client.clone();
+        let session_id = session_id.clone();
+        let jobs_rx = jobs_rx.clone();
+        let results_tx = results_tx.clone();
+        thread::spawn(move || {
+            loop {
+                let job = jobs_rx.lock().unwrap().recv();
+                let (offset, chunk) = match job {
+                    Ok(job) => job,
+                    Err(_) => break,
+                };
+                let mut cursor = UploadSessionCursor::new(&session_id, offset);
+                let result = append_chunk(&client, &mut cursor, &chunk, false, true).map(|_| chunk.len() as u64);
+                if results_tx.send(result).is_err() {
+                    break;
+                }
+            }
+        })
+    }).collect::<Vec<_>>();
+    // Only the workers hold the receiver, so sending fails once they have all stopped
+    drop(jobs_rx);
+    drop(results_tx);
+
+    // The last chunk is held back, as it has to close the session after all the others are in
+    let mut offset = 0;
+    let mut done = 0;
+    let mut pending = 0;
+    let mut failure = None;
+    let mut workers_stopped = false;
+    while !next.is_empty() {
+        if let Err(e) = transfer.check() {
+            failure = Some(e);
+            break;
+        }
+        let len = chunk.len() as u64;
+        let job = (offset, mem::replace(&mut chunk, mem::replace(&mut next, vec![])));
+        if jobs.send(job).is_err() {
+            workers_stopped = true;
+            break;
+        }
+        offset += len;
+        pending += 1;
+        while let Ok(result) = results.try_recv() {
+            pending -= 1;
+            match result {
+                Ok(len) => {
+                    done += len;
+                    transfer.report(done, None);
+                },
+                Err(e) => failure = failure.or(Some(e)),
+            }
+        }
+        if failure.is_some() {
+            break;
+        }
+        next = match read_chunk(&mut reader, chunk_size) {
+            Ok(next) => next,
+            Err(e) => {
+                failure = Some(e);
+                break;
+            },
+        };
+    }
+    drop(jobs);
+    for result in results.iter() {
+        pending -= 1;
+        match result {
+            Ok(len) => {
+                done += len;
+                transfer.report(done, None);
+            },
+            Err(e) => failure = failure.or(Some(e)),
+        }
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+    // A worker that panicked never sends the result of the chunk it had
+    if (workers_stopped || pending > 0) && failure.is_none() {
+        failure = Some(ApiError::UploadThreadPanicked);
+    }
+    if let Some(e) = failure {
+        return Err(e);
+    }
+
+    try!(transfer.check());
+    let mut cursor = UploadSessionCursor::new(&session_id, offset);
+    try!(append_chunk(client, &mut cursor, &chunk, true, true));
+    transfer.report(cursor.offset, Some(cursor.offset));
+    session_finish(client, Body::Empty, &cursor, &commit_info(path, &options))
+}
+
+/// Appends `chunk` at the cursor and moves the cursor past it
+///
+/// If the chunk fails in a sequential session, it is resent from the offset dropbox reports in
+/// its `incorrect_offset` error, as part of the chunk may have been received. A `concurrent`
+/// session only takes whole chunks at their own offset, so there the chunk is always resent
+/// whole. With `close` set, the session is closed once the chunk is in.
+fn append_chunk<T>(client: &T, cursor: &mut UploadSessionCursor, chunk: &[u8], close: bool, concurrent: bool) -> Result<()>
+                where T: DropboxClient
+{
+    let start = cursor.offset;
+    let end = start + chunk.len() as u64;
+    let mut attempts = 0;
+    while cursor.offset < end {
+        attempts += 1;
+        let sent = (cursor.offset - start) as usize;
+        match session_append(client, Body::Bytes(&chunk[sent..]), cursor, close) {
+            Ok(()) => cursor.offset = end,
+            Err(ApiError::UploadSessionLookupError(UploadSessionLookupError::IncorrectOffset { correct_offset }, _))
+                    if !concurrent && correct_offset >= start && correct_offset <= end && attempts < CHUNK_ATTEMPTS => {
+                cursor.offset = correct_offset;
+            },
+            Err(ApiError::HttpError(_)) |
+            Err(ApiError::IoError(..)) |
+            Err(ApiError::RetriesExhausted(..)) if attempts < CHUNK_ATTEMPTS => {},
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Reads up to `size` bytes, returning fewer only at the end of the reader
+///
+/// The chunk grows as it is read, rather than reserving `size` bytes up front for a reader that
+/// may turn out to be much shorter.
+fn read_chunk<R: Read>(reader: &mut R, size: usize) -> Result<Vec<u8>> {
+    let mut chunk = Vec::new();
+    try!(reader.by_ref().take(size as u64).read_to_end(&mut chunk));
+    Ok(chunk)
+}
+
+fn commit_info(path: &str, options: &UploadOptions) -> CommitInfo {
+    let commit = CommitInfo::new(path)
+                     .mode(options.mode.clone())
+                     .autorename(options.autorename)
+                     .mute(options.mute);
+    match options.client_modified {
+        Some(client_modified) => commit.client_modified(client_modified),
+        None => commit,
+    }
+}
+
+/// Headers for a single request upload, whose arguments are the same as a `CommitInfo`'s
+fn upload_headers(path: &str, options: &UploadOptions) -> BTreeMap<String, String> {
+    arg_headers(&commit_info(path, options).to_json())
+}
+
+/// Encodes `arg` as JSON for the `Dropbox-API-Arg` header
+///
+/// Header values must be ASCII, so DEL and everything outside of ASCII is escaped as `\uXXXX`,
+/// with characters beyond the BMP written as UTF-16 surrogate pairs.
+fn api_arg<A: Encodable>(arg: &A) -> String {
+    let json = json::encode(arg).unwrap();
+    let mut escaped = String::with_capacity(json.len());
+    for unit in json.encode_utf16() {
+        if unit < 0x7f {
+            escaped.push(unit as u8 as char);
+        } else {
+            escaped.push_str(&format!("\\u{:04x}", unit));
+        }
+    }
+    escaped
+}
+
+/// Headers for a content request, with `arg` as the `Dropbox-API-Arg`
+fn arg_headers<A: Encodable>(arg: &A) -> BTreeMap<String, String> {
     let mut headers = BTreeMap::new();
-    headers.insert("Dropbox-API-Arg".to_string(), json::encode(&map).unwrap());
+    headers.insert("Dropbox-API-Arg".to_string(), api_arg(arg));
     headers.insert("Content-Type".to_string(), "application/octet-stream".to_string());
-    let resp = try!(client.content("files/upload", &mut headers, Some(contents.to_owned())));
-    json::decode(&resp.body).map_err(ApiError::from)
+    headers
 }
 
-/// TODO implement
-pub fn upload_session_append<T, U>(client: &T, f: U, session_id: &str, offset: usize) -> Result<()>
+/// Append a chunk of data to an upload session
+///
+/// Everything that can be read from `f` is uploaded at the position of `cursor`. Pass `close` as
+/// `true` when this is the last chunk. Returns the cursor to upload the next chunk at.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files::{self, CommitInfo};
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let cursor = try!(files::upload_session_start(&client, &b"first chunk, "[..]));
+/// let cursor = try!(files::upload_session_append(&client, &b"second chunk, "[..], &cursor, false));
+/// let commit = CommitInfo::new("/path/to/file");
+/// let metadata = try!(files::upload_session_finish(&client, &b"last chunk"[..], &cursor, &commit));
+/// ```
+pub fn upload_session_append<T, U>(client: &T, f: U, cursor: &UploadSessionCursor, close: bool) -> Result<UploadSessionCursor>
                 where T: DropboxClient, U: io::Read
 {
-    Ok(())
+    let mut reader = CountingReader { inner: f, count: 0 };
+    try!(session_append(client, Body::Reader(&mut reader), cursor, close));
+    Ok(UploadSessionCursor::new(&cursor.session_id, cursor.offset + reader.count))
 }
 
-/// TODO implement
-pub fn upload_session_finish<T, U>(client: &T, f: U, cursor: &UploadSessionCursor, commit: &CommitInfo) -> Result<Metadata>
+/// Commit many upload sessions at once
+///
+/// Committing sessions one by one with `upload_session_finish` quickly runs into
+/// `too_many_write_operations` errors, as each commit locks the namespace. This commits them all
+/// in one go. Every session must have been closed, by passing `close` as `true` to the
+/// `upload_session_append` that sent its last chunk. At most 1000 entries can be committed at
+/// once; larger batches fail with `ApiError::BatchTooLarge` without being sent.
+///
+/// Dropbox usually answers with a job to poll; `upload_session_finish_batch_wait` takes care of
+/// that.
+///
+/// # Example
+///
+/// ```ignore
+/// use std::env;
+/// use dbox::client::Client;
+/// use dbox::files::{self, CommitInfo};
+///
+/// let client = Client::new(env::var("DROPBOX_TOKEN"));
+/// let cursor = try!(files::upload_session_start(&client, &b"file contents"[..]));
+/// let cursor = try!(files::upload_session_append(&client, &b""[..], &cursor, true));
+/// let launch = try!(files::upload_session_finish_batch(&client, &[(cursor, CommitInfo::new("/path/to/file"))]));
+/// let results = try!(files::upload_session_finish_batch_wait(&client, launch));
+/// ```
+pub fn upload_session_finish_batch<T>(client: &T, entries: &[(UploadSessionCursor, CommitInfo)]) -> Result<UploadSessionFinishBatchLaunch>
+                where T: DropboxClient
+{
+    if entries.len() > BATCH_MAX_ENTRIES {
+        return Err(ApiError::BatchTooLarge(entries.len()));
+    }
+    let entries = entries.iter().map(|&(ref cursor, ref commit)| {
+        let mut entry = BTreeMap::new();
+        entry.insert("cursor".to_string(), cursor.to_json());
+        entry.insert("commit".to_string(), commit.to_json());
+        json::Json::Object(entry)
+    }).collect();
+    let mut map = BTreeMap::new();
+    map.insert("entries", json::Json::Array(entries));
+    let mut headers = BTreeMap::new();
+    let resp = try!(client.api("files/upload_session/finish_batch", &mut headers, Some(map)));
+    let result = try!(parse_json(&try!(resp.text())));
+    match result.find(".tag").and_then(|tag| tag.as_string()) {
+        Some("async_job_id") => {
+            match result.find("async_job_id").and_then(|id| id.as_string()) {
+                Some(id) => Ok(UploadSessionFinishBatchLaunch::AsyncJobId(id.to_owned())),
+                None => Err(ApiError::ClientError),
+            }
+        },
+        Some("complete") => Ok(UploadSessionFinishBatchLaunch::Complete(try!(decode_batch_entries(&result)))),
+        _ => Err(ApiError::ClientError),
+    }
+}
+
+/// Check on the status of a job started by `upload_session_finish_batch`
+pub fn upload_session_finish_batch_check<T>(client: &T, async_job_id: &str) -> Result<UploadSessionFinishBatchJobStatus>
+                where T: DropboxClient
+{
+    let mut map = BTreeMap::new();
+    map.insert("async_job_id", json::Json::String(async_job_id.to_owned()));
+    let mut headers = BTreeMap::new();
+    let resp = try!(client.api("files/upload_session/finish_batch/check", &mut headers, Some(map))
+                        .map_err(|e| route_error(e, ApiError::PollError)));
+    let result = try!(parse_json(&try!(resp.text())));
+    match result.find(".tag").and_then(|tag| tag.as_string()) {
+        Some("in_progress") => Ok(UploadSessionFinishBatchJobStatus::InProgress),
+        Some("complete") => Ok(UploadSessionFinishBatchJobStatus::Complete(try!(decode_batch_entries(&result)))),
+        _ => Err(ApiError::ClientError),
+    }
+}
+
+/// Wait for a batch started by `upload_session_finish_batch` to complete
+///
+/// Polls the job every second until it is done, and returns the outcome for each session, in the
+/// order they were passed to `upload_session_finish_batch`. A job that is still in progress after
+/// ten minutes fails with `ApiError::JobTimedOut`; it may still complete, and can be checked on
+/// with `upload_session_finish_batch_check`.
+pub fn upload_session_finish_batch_wait<T>(client: &T, launch: UploadSessionFinishBatchLaunch) -> Result<Vec<UploadSessionFinishBatchResultEntry>>
+                where T: DropboxClient
+{
+    let async_job_id = match launch {
+        UploadSessionFinishBatchLaunch::Complete(entries) => return Ok(entries),
+        UploadSessionFinishBatchLaunch::AsyncJobId(id) => id,
+    };
+    for _ in 0..BATCH_POLL_ATTEMPTS {
+        match try!(upload_session_finish_batch_check(client, &async_job_id)) {
+            UploadSessionFinishBatchJobStatus::Complete(entries) => return Ok(entries),
+            UploadSessionFinishBatchJobStatus::InProgress => {
+                thread::sleep(Duration::from_millis(BATCH_POLL_INTERVAL_MS));
+            },
+        }
+    }
+    Err(ApiError::JobTimedOut(async_job_id))
+}
+
+fn decode_batch_entries(result: &json::Json) -> Result<Vec<UploadSessionFinishBatchResultEntry>> {
+    let entries = match result.find("entries").and_then(|entries| entries.as_array()) {
+        Some(entries) => entries,
+        None => return Err(ApiError::ClientError),
+    };
+    entries.iter().map(|entry| {
+        match entry.find(".tag").and_then(|tag| tag.as_string()) {
+            // The metadata of the new file is inlined next to the tag
+            Some("success") => Ok(UploadSessionFinishBatchResultEntry::Success(try!(decode_json(entry)))),
+            Some("failure") => {
+                match entry.find("failure") {
+                    Some(failure) => Ok(UploadSessionFinishBatchResultEntry::Failure(try!(decode_json(failure)))),
+                    None => Err(ApiError::ClientError),
+                }
+            },
+            _ => Err(ApiError::ClientError),
+        }
+    }).collect()
+}
+
+fn parse_json(s: &str) -> Result<json::Json> {
+    json::Json::from_str(s).map_err(|e| ApiError::DecodeError(format!("{}", e)))
+}
+
+fn decode_json<D: Decodable>(value: &json::Json) -> Result<D> {
+    let mut decoder = json::Decoder::new(value.clone());
+    Decodable::decode(&mut decoder).map_err(ApiError::from)
+}
+
+/// Finish an upload session, uploading the last chunk and saving the file as described by `commit`
+pub fn upload_session_finish<T, U>(client: &T, f: U, cursor: &UploadSessionCursor, commit: &CommitInfo) -> Result<FileMetadata>
                 where T: DropboxClient,
                       U: io::Read
 {
-    Ok(Default::default())
+    let mut f = f;
+    session_finish(client, Body::Reader(&mut f), cursor, commit)
 }
 
-/// TODO implement
-pub fn upload_session_start<T, U>(client: &T, f: U) -> Result<String>
+/// Start an upload session, uploading the first chunk of the file
+///
+/// Sessions allow uploading files that are larger than the 150MB a single `upload` can take, by
+/// uploading them in chunks. Returns the cursor to upload the next chunk at.
+pub fn upload_session_start<T, U>(client: &T, f: U) -> Result<UploadSessionCursor>
                 where T: DropboxClient,
                       U: io::Read
 {
-    Ok("".to_string())
+    let mut reader = CountingReader { inner: f, count: 0 };
+    let session_id = try!(session_start(client, Body::Reader(&mut reader), false, false));
+    Ok(UploadSessionCursor::new(&session_id, reader.count))
+}
+
+fn session_start<T>(client: &T, body: Body, close: bool, concurrent: bool) -> Result<String>
+                where T: DropboxClient
+{
+    let mut map = BTreeMap::new();
+    map.insert("close", json::Json::Boolean(close));
+    if concurrent {
+        let mut session_type = BTreeMap::new();
+        session_type.insert(".tag".to_owned(), json::Json::String("concurrent".to_owned()));
+        map.insert("session_type", json::Json::Object(session_type));
+    }
+    let mut headers = arg_headers(&map);
+    let resp = try!(client.content("files/upload_session/start", &mut headers, body));
+    let result: UploadSessionStartResult = try!(json::decode(&try!(resp.text())));
+    Ok(result.session_id)
+}
+
+fn session_append<T>(client: &T, body: Body, cursor: &UploadSessionCursor, close: bool) -> Result<()>
+                where T: DropboxClient
+{
+    let mut map = BTreeMap::new();
+    map.insert("cursor", cursor.to_json());
+    map.insert("close", json::Json::Boolean(close));
+    let mut headers = arg_headers(&map);
+    try!(client.content("files/upload_session/append_v2", &mut headers, body)
+               .map_err(|e| route_error(e, ApiError::UploadSessionLookupError)));
+    Ok(())
+}
+
+fn session_finish<T>(client: &T, body: Body, cursor: &UploadSessionCursor, commit: &CommitInfo) -> Result<FileMetadata>
+                where T: DropboxClient
+{
+    let mut map = BTreeMap::new();
+    map.insert("cursor", cursor.to_json());
+    map.insert("commit", commit.to_json());
+    let mut headers = arg_headers(&map);
+    let resp = try!(client.content("files/upload_session/finish", &mut headers, body)
+                        .map_err(|e| route_error(e, ApiError::UploadSessionFinishError)));
+    json::decode(&try!(resp.text())).map_err(ApiError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{Result, Response, DropboxClient, ApiError, ErrorResponse, Body, Endpoint};
+    use std::cell::Cell;
+    use std::collections::BTreeMap;
+    use std::env;
+    use std::fs::{self, File};
+    use std::io::{Cursor, Read, Write};
+    use std::path::{Path, PathBuf};
+    use std::str;
+    use std::sync::Mutex;
+    use chrono::{UTC, TimeZone};
+    use rand;
+    use rustc_serialize::json::Json;
+
+    /// A request the fake client was sent: the route, its headers and its body
+    type Request = (String, BTreeMap<String, String>, Vec<u8>);
+
+    /// Answers every request with `handler`, keeping a log of what it was sent
+    struct FakeClient {
+        handler: Box<Fn(&str, &BTreeMap<String, String>, &[u8]) -> Result<Response>>,
+        requests: Mutex<Vec<Request>>,
+    }
+
+    impl FakeClient {
+        fn new<F>(handler: F) -> FakeClient
+                where F: Fn(&str, &BTreeMap<String, String>, &[u8]) -> Result<Response> + 'static
+        {
+            FakeClient {
+                handler: Box::new(handler),
+                requests: Mutex::new(vec![]),
+            }
+        }
+
+        /// The `header` of every request made to `route`
+        fn headers(&self, route: &str, header: &str) -> Vec<String> {
+            self.requests.lock().unwrap().iter()
+                .filter(|&&(ref url, _, _)| url == route)
+                .map(|&(_, ref headers, _)| headers.get(header).cloned().unwrap_or_default())
+                .collect()
+        }
+    }
+
+    impl DropboxClient for FakeClient {
+        fn access_token(&self) -> &str {
+            ""
+        }
+
+        fn request(&self, _: Endpoint, url: &str, headers: &mut BTreeMap<String, String>, body: Body) -> Result<Response> {
+            let mut bytes = vec![];
+            match body {
+                Body::Empty => {},
+                Body::Bytes(b) => bytes.extend_from_slice(b),
+                Body::Reader(r) => { try!(r.read_to_end(&mut bytes)); },
+            }
+            self.requests.lock().unwrap().push((url.to_owned(), headers.clone(), bytes.clone()));
+            (self.handler)(url, headers, &bytes)
+        }
+    }
+
+    fn file_json(rev: &str, size: usize) -> String {
+        format!(r#"{{".tag": "file", "name": "f", "path_lower": "/f", "id": "id:f", "size": {}, "rev": "{}",
+                    "client_modified": "2015-05-12T15:50:38Z", "server_modified": "2015-05-12T15:50:38Z"}}"#, size, rev)
+    }
+
+    /// Serves `contents` as revision `rev`, though downloads come back as `download_rev`, the way
+    /// they do when the file changes between the metadata and the download
+    fn fake_file(contents: &[u8], rev: &str, download_rev: &str) -> FakeClient {
+        let contents = contents.to_vec();
+        let (rev, download_rev) = (rev.to_owned(), download_rev.to_owned());
+        FakeClient::new(move |url, headers, _| {
+            match url {
+                "files/get_metadata" => Ok(Response::new(200, None, Cursor::new(file_json(&rev, contents.len())))),
+                "files/download" => {
+                    let (status, start) = match headers.get("Range") {
+                        Some(range) => (206, range["bytes=".len()..].trim_right_matches('-').parse::<usize>().unwrap()),
+                        None => (200, 0),
+                    };
+                    let body = contents[start..].to_vec();
+                    Ok(Response::new(status, Some(file_json(&download_rev, contents.len())), Cursor::new(body)))
+                },
+                _ => panic!("Unexpected request to {}", url),
+            }
+        })
+    }
+
+    /// Runs upload sessions, failing the `n`th append, counting from 0, with `append(n)`
+    fn fake_session<F>(append: F) -> FakeClient
+                where F: Fn(usize) -> Option<ApiError> + 'static
+    {
+        let appends = Cell::new(0);
+        FakeClient::new(move |url, _, body| {
+            match url {
+                "files/upload" => Ok(Response::new(200, None, Cursor::new(file_json("a1", body.len())))),
+                "files/upload_session/start" => Ok(Response::new(200, None, Cursor::new(r#"{"session_id": "s"}"#))),
+                "files/upload_session/append_v2" => {
+                    let n = appends.get();
+                    appends.set(n + 1);
+                    match append(n) {
+                        Some(e) => Err(e),
+                        None => Ok(Response::new(200, None, Cursor::new("null"))),
+                    }
+                },
+                "files/upload_session/finish" => Ok(Response::new(200, None, Cursor::new(file_json("a1", 0)))),
+                _ => panic!("Unexpected request to {}", url),
+            }
+        })
+    }
+
+    fn incorrect_offset(correct_offset: u64) -> ApiError {
+        let body = format!(r#"{{"error_summary": "incorrect_offset/..",
+                                "error": {{".tag": "incorrect_offset", "correct_offset": {}}}}}"#, correct_offset);
+        ApiError::ResponseError(ErrorResponse::new(409, "files/upload_session/append_v2", &body))
+    }
+
+    /// The cursor offset and body of every append the fake client was sent
+    fn appends(client: &FakeClient) -> Vec<(u64, Vec<u8>)> {
+        client.requests.lock().unwrap().iter()
+            .filter(|&&(ref url, _, _)| url == "files/upload_session/append_v2")
+            .map(|&(_, ref headers, ref body)| {
+                let arg = Json::from_str(&headers["Dropbox-API-Arg"]).unwrap();
+                (arg.find_path(&["cursor", "offset"]).unwrap().as_u64().unwrap(), body.clone())
+            })
+            .collect()
+    }
+
+    fn temp_dir() -> PathBuf {
+        let dir = env::temp_dir().join(format!("dbox-test-{}", rand::random::<u64>()));
+        fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+    fn write_file(path: &Path, contents: &[u8]) {
+        File::create(path).unwrap().write_all(contents).unwrap();
+    }
+
+    fn read_file(path: &Path) -> Vec<u8> {
+        let mut contents = vec![];
+        File::open(path).unwrap().read_to_end(&mut contents).unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_api_arg() {
+        let mut map = BTreeMap::new();
+        map.insert("path", "/caf\u{e9}/\u{1f600}\u{7f}.txt");
+        assert_eq!(api_arg(&map), r#"{"path":"/caf\u00e9/\ud83d\ude00\u007f.txt"}"#);
+        assert_eq!(arg_headers(&map)["Dropbox-API-Arg"], api_arg(&map));
+
+        let client = fake_file(b"hello world", "a1", "a1");
+        download(&client, "/caf\u{e9}.txt").unwrap();
+        assert_eq!(client.headers("files/download", "Dropbox-API-Arg"), vec![r#"{"path":"/caf\u00e9.txt"}"#]);
+    }
+
+    #[test]
+    fn test_commit_info() {
+        let modified = UTC.ymd(2015, 5, 12).and_hms(15, 50, 38);
+        let commit = CommitInfo::new("/f").mode(WriteMode::Overwrite).autorename(true).client_modified(modified);
+        assert_eq!(commit.to_json().to_string(),
+                   r#"{"autorename":true,"client_modified":"2015-05-12T15:50:38Z","mode":{".tag":"overwrite"},"mute":false,"path":"/f"}"#);
+        assert_eq!(CommitInfo::new("/f").to_json().find("client_modified"), None);
+
+        let client = fake_session(|_| None);
+        let options = UploadOptions { client_modified: Some(modified), .. Default::default() };
+        upload_with_options(&client, b"hello", "/f", options).unwrap();
+        let arg = Json::from_str(&client.headers("files/upload", "Dropbox-API-Arg")[0]).unwrap();
+        assert_eq!(arg.find("client_modified").and_then(|date| date.as_string()), Some("2015-05-12T15:50:38Z"));
+    }
+
+    #[test]
+    fn test_write_mode() {
+        assert_eq!(WriteMode::Add.to_json().to_string(), r#"{".tag":"add"}"#);
+        assert_eq!(WriteMode::Overwrite.to_json().to_string(), r#"{".tag":"overwrite"}"#);
+        assert_eq!(WriteMode::Update("a1b2".to_owned()).to_json().to_string(), r#"{".tag":"update","update":"a1b2"}"#);
+
+        let client = fake_session(|_| None);
+        let options = UploadOptions { mode: WriteMode::Update("a1b2".to_owned()), .. Default::default() };
+        upload_with_options(&client, b"hello", "/f", options).unwrap();
+        let arg = Json::from_str(&client.headers("files/upload", "Dropbox-API-Arg")[0]).unwrap();
+        assert_eq!(arg.find("mode"), Some(&WriteMode::Update("a1b2".to_owned()).to_json()));
+    }
+
+    fn entry(json: &str) -> Metadata {
+        json::decode(json).unwrap()
+    }
+
+    #[test]
+    fn test_watcher_classify() {
+        let client = FakeClient::new(|url, _, _| {
+            assert_eq!(url, "files/list_folder");
+            Ok(Response::new(200, None, Cursor::new(r#"{"entries": [
+                {".tag": "folder", "name": "dir", "path_lower": "/dir", "id": "id:d"},
+                {".tag": "folder", "name": "sub", "path_lower": "/dir/sub", "id": "id:s"},
+                {".tag": "folder", "name": "dirt", "path_lower": "/dirt", "id": "id:t"}
+            ], "cursor": "c", "has_more": false}"#)))
+        });
+        let mut watcher = Watcher::new(&client, "", ListFolderOptions::new().recursive(true)).unwrap();
+        assert_eq!(watcher.cursor(), "c");
+
+        let added = entry(r#"{".tag": "folder", "name": "new", "path_lower": "/new", "id": "id:n"}"#);
+        assert_eq!(watcher.classify(added.clone()), Change::Added(added.clone()));
+        assert_eq!(watcher.classify(added.clone()), Change::Modified(added.clone()));
+
+        // Deleting a folder forgets everything in it, but not its siblings
+        match watcher.classify(entry(r#"{".tag": "deleted", "name": "dir", "path_lower": "/dir"}"#)) {
+            Change::Deleted(deleted) => assert_eq!(deleted.path_lower(), Some("/dir")),
+            change => panic!("Unexpected change: {:?}", change),
+        }
+        assert_eq!(watcher.known.iter().map(|path| &path[..]).collect::<Vec<_>>(), vec!["/dirt", "/new"]);
+        let sub = entry(r#"{".tag": "folder", "name": "sub", "path_lower": "/dir/sub", "id": "id:s"}"#);
+        assert_eq!(watcher.classify(sub.clone()), Change::Added(sub));
+
+        // A watcher from a cursor can't tell which entries were already there
+        let mut watcher = Watcher::from_cursor(&client, "c");
+        assert_eq!(watcher.classify(added.clone()), Change::Modified(added));
+    }
+
+    #[test]
+    fn test_longpoll_timeout() {
+        let client = FakeClient::new(|url, _, _| {
+            assert_eq!(url, "files/list_folder/longpoll");
+            Ok(Response::new(200, None, Cursor::new(r#"{"changes": false}"#)))
+        });
+        for &timeout in &[0, 30, 60, 480, 3600] {
+            list_folder_longpoll(&client, "c", timeout).unwrap();
+        }
+        let timeouts = client.requests.lock().unwrap().iter().map(|&(_, _, ref body)| {
+            Json::from_str(str::from_utf8(body).unwrap()).unwrap().find("timeout").and_then(|timeout| timeout.as_u64())
+        }).collect::<Vec<_>>();
+        assert_eq!(timeouts, vec![Some(30), Some(30), Some(60), Some(480), Some(480)]);
+    }
+
+    #[test]
+    fn test_upload_threshold() {
+        let options = UploadOptions { upload_threshold: 11, chunk_size: 4, .. Default::default() };
+        let client = fake_session(|_| None);
+        upload_reader(&client, &b"hello world"[..], "/f", options.clone()).unwrap();
+        assert_eq!(client.headers("files/upload", "Content-Type").len(), 1);
+        assert!(appends(&client).is_empty());
+
+        let client = fake_session(|_| None);
+        upload_reader(&client, &b"hello world"[..], "/f", UploadOptions { upload_threshold: 10, .. options }).unwrap();
+        assert!(client.headers("files/upload", "Content-Type").is_empty());
+        assert_eq!(appends(&client).iter().map(|&(offset, _)| offset).collect::<Vec<_>>(), vec![0, 4, 8]);
+        let finish = client.headers("files/upload_session/finish", "Dropbox-API-Arg");
+        assert_eq!(Json::from_str(&finish[0]).unwrap().find_path(&["cursor", "offset"]), Some(&Json::U64(11)));
+    }
+
+    #[test]
+    fn test_upload_session_resends_chunks() {
+        // Dropbox got half of the second chunk, then the third is lost once on the way
+        let options = UploadOptions { upload_threshold: 4, chunk_size: 4, .. Default::default() };
+        let client = fake_session(|n| {
+            match n {
+                1 => Some(incorrect_offset(6)),
+                3 => Some(ApiError::HttpError("connection reset".to_owned())),
+                _ => None,
+            }
+        });
+        upload_reader(&client, &b"hello world"[..], "/f", options).unwrap();
+        assert_eq!(appends(&client), vec![
+            (0, b"hell".to_vec()),
+            (4, b"o wo".to_vec()),
+            (6, b"wo".to_vec()),
+            (8, b"rld".to_vec()),
+            (8, b"rld".to_vec()),
+        ]);
+    }
+
+    #[test]
+    fn test_concurrent_session_resends_whole_chunks() {
+        let client = fake_session(|n| {
+            match n {
+                0 => Some(ApiError::HttpError("connection reset".to_owned())),
+                2 => Some(incorrect_offset(6)),
+                _ => None,
+            }
+        });
+        let mut cursor = UploadSessionCursor::new("s", 4);
+        append_chunk(&client, &mut cursor, b"o wo", false, true).unwrap();
+        assert_eq!(cursor.offset(), 8);
+        assert_eq!(appends(&client), vec![(4, b"o wo".to_vec()), (4, b"o wo".to_vec())]);
+
+        let mut cursor = UploadSessionCursor::new("s", 4);
+        match append_chunk(&client, &mut cursor, b"o wo", false, true) {
+            Err(ApiError::UploadSessionLookupError(UploadSessionLookupError::IncorrectOffset { correct_offset: 6 }, _)) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert_eq!(appends(&client).len(), 3);
+    }
+
+    #[test]
+    fn test_upload_session_gives_up() {
+        let options = UploadOptions { upload_threshold: 4, chunk_size: 4, .. Default::default() };
+        let client = fake_session(|_| Some(ApiError::HttpError("connection reset".to_owned())));
+        match upload_reader(&client, &b"hello world"[..], "/f", options) {
+            Err(ApiError::HttpError(_)) => {},
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert_eq!(appends(&client).len(), CHUNK_ATTEMPTS as usize);
+        assert!(client.headers("files/upload_session/finish", "Dropbox-API-Arg").is_empty());
+    }
+
+    #[test]
+    fn test_finish_batch() {
+        let body = r#"{".tag": "complete", "entries": [
+            {".tag": "success", "name": "f", "path_lower": "/f", "id": "id:f", "size": 11, "rev": "a1",
+             "client_modified": "2015-05-12T15:50:38Z", "server_modified": "2015-05-12T15:50:38Z"},
+            {".tag": "failure", "failure": {".tag": "lookup_failed",
+                                            "lookup_failed": {".tag": "incorrect_offset", "correct_offset": 4}}},
+            {".tag": "failure", "failure": {".tag": "path", "path": {".tag": "conflict", "conflict": {".tag": "file"}}}}
+        ]}"#;
+        let client = FakeClient::new(move |url, _, _| {
+            assert_eq!(url, "files/upload_session/finish_batch/check");
+            Ok(Response::new(200, None, Cursor::new(body)))
+        });
+        let entries = match upload_session_finish_batch_check(&client, "job").unwrap() {
+            UploadSessionFinishBatchJobStatus::Complete(entries) => entries,
+            status => panic!("Unexpected status: {:?}", status),
+        };
+        match entries[0] {
+            UploadSessionFinishBatchResultEntry::Success(ref metadata) => {
+                assert_eq!((metadata.rev(), metadata.size()), ("a1", 11));
+            },
+            ref entry => panic!("Unexpected entry: {:?}", entry),
+        }
+        assert_eq!(&entries[1..], &[
+            UploadSessionFinishBatchResultEntry::Failure(UploadSessionFinishError::LookupFailed(
+                UploadSessionLookupError::IncorrectOffset { correct_offset: 4 })),
+            UploadSessionFinishBatchResultEntry::Failure(UploadSessionFinishError::Path(
+                WriteError::Conflict(WriteConflictError::File))),
+        ]);
+
+        let entry = (UploadSessionCursor::new("s", 0), CommitInfo::new("/f"));
+        let entries = vec![entry; BATCH_MAX_ENTRIES + 1];
+        assert_eq!(upload_session_finish_batch(&client, &entries), Err(ApiError::BatchTooLarge(BATCH_MAX_ENTRIES + 1)));
+        assert_eq!(client.requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_download_to_transfer() {
+        let canceller = Transfer::new();
+        let transfer = canceller.clone().on_progress(move |_, _| canceller.cancel());
+        let mut written = vec![];
+        let client = fake_file(b"hello world", "a1", "a1");
+        assert_eq!(download_to(&client, "/f", &mut written, &transfer).unwrap_err(), ApiError::Cancelled);
+        assert_eq!(written, b"hello world");
+
+        let client = FakeClient::new(|_, _, _| {
+            let metadata = file_json("a1", 11).replace(r#""rev""#, r#""content_hash": "abc", "rev""#);
+            Ok(Response::new(200, Some(metadata), Cursor::new("hello world")))
+        });
+        match download_to(&client, "/f", io::sink(), &Transfer::new().verify(true)) {
+            Err(ApiError::ContentHashMismatch(expected, _)) => assert_eq!(expected, "abc"),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert!(download_to(&client, "/f", io::sink(), &Transfer::new()).is_ok());
+    }
+
+    #[test]
+    fn test_download_folder() {
+        let client = FakeClient::new(|url, _, _| {
+            assert_eq!(url, "files/get_metadata");
+            Ok(Response::new(200, None, Cursor::new(r#"{".tag": "folder", "name": "dir", "path_lower": "/dir", "id": "id:d"}"#)))
+        });
+        match get_metadata(&client, "/dir", false) {
+            Ok(Metadata::Folder(ref folder)) => assert_eq!(folder.name(), "dir"),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        let dir = temp_dir();
+        let result = download_to_file(&client, dir.join("dir").to_str().unwrap(), "/dir", &Transfer::new());
+        assert_eq!(result.unwrap_err(), ApiError::NotFile("/dir".to_owned()));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_decode_error() {
+        let client = FakeClient::new(|_, _, _| {
+            let metadata = file_json("a1", 11).replace("2015-05-12T15:50:38Z", "yesterday");
+            Ok(Response::new(200, None, Cursor::new(metadata)))
+        });
+        match get_metadata(&client, "/f", false) {
+            Err(ApiError::DecodeError(message)) => assert!(message.contains("Invalid timestamp yesterday"), message),
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_download_resumes_partial() {
+        let dir = temp_dir();
+        let dest = dir.join("file.txt");
+        let partial = dir.join(".file.txt.a1.dbox-part");
+        write_file(&partial, b"hello ");
+
+        let client = fake_file(b"hello world", "a1", "a1");
+        let (metadata, size) = download_to_file(&client, dest.to_str().unwrap(), "/f", &Transfer::new()).unwrap();
+        assert_eq!(metadata.rev(), "a1");
+        assert_eq!(size, 11);
+        assert_eq!(client.headers("files/download", "Range"), vec!["bytes=6-"]);
+        assert_eq!(read_file(&dest), b"hello world");
+        assert!(!partial.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_download_restarts_oversized_partial() {
+        let dir = temp_dir();
+        let dest = dir.join("file.txt");
+        let partial = dir.join(".file.txt.a1.dbox-part");
+        write_file(&partial, b"something longer than the file");
+
+        let client = fake_file(b"hello world", "a1", "a1");
+        download_to_file(&client, dest.to_str().unwrap(), "/f", &Transfer::new()).unwrap();
+        assert_eq!(client.headers("files/download", "Range"), vec!["bytes=0-"]);
+        assert_eq!(read_file(&dest), b"hello world");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_download_revision_changed() {
+        let dir = temp_dir();
+        let dest = dir.join("file.txt");
+        let partial = dir.join(".file.txt.a1.dbox-part");
+        write_file(&partial, b"hello ");
+
+        let client = fake_file(b"hello world", "a1", "a2");
+        match download_to_file(&client, dest.to_str().unwrap(), "/f", &Transfer::new()) {
+            Err(ApiError::RevisionChanged(expected, actual)) => {
+                assert_eq!((&expected[..], &actual[..]), ("a1", "a2"));
+            },
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        assert!(!partial.exists());
+        assert!(!dest.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_download_keeps_other_files_partials() {
+        let dir = temp_dir();
+        let stale = dir.join(".file.a0.dbox-part");
+        let other = dir.join(".file.txt.a0.dbox-part");
+        write_file(&stale, b"old");
+        write_file(&other, b"hello ");
+
+        let client = fake_file(b"hello world", "a1", "a1");
+        download_to_file(&client, dir.join("file").to_str().unwrap(), "/f", &Transfer::new()).unwrap();
+        assert!(!stale.exists());
+        assert_eq!(read_file(&other), b"hello ");
+
+        let partial = dir.join(".file.a1.dbox-part");
+        write_file(&partial, b"hello ");
+        download_to_file(&client, dir.join("file.txt").to_str().unwrap(), "/f", &Transfer::new()).unwrap();
+        assert!(!other.exists());
+        assert_eq!(read_file(&partial), b"hello ");
+        assert_eq!(read_file(&dir.join("file.txt")), b"hello world");
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -30,18 +30,23 @@
 
 #[cfg(feature = "hyper-client")] extern crate hyper;
 extern crate chrono;
+extern crate crypto;
+extern crate filetime;
 extern crate rustc_serialize;
 
-#[cfg(test)] extern crate rand;
+extern crate rand;
 
 use std::convert::From;
 use std::fmt;
+use std::io::{self, Read};
 use std::collections::BTreeMap;
+use rustc_serialize::json;
 
 #[doc(hidden)]
 pub enum Endpoint {
     Api,
     Content,
+    Notify,
 }
 
 impl fmt::Display for Endpoint {
@@ -49,52 +54,126 @@ impl fmt::Display for Endpoint {
         match *self {
             Endpoint::Api => write!(f, "api"),
             Endpoint::Content => write!(f, "content"),
+            Endpoint::Notify => write!(f, "notify"),
         }
     }
 }
 
+/// Body of a request to the Dropbox API
+pub enum Body<'a> {
+    Empty,
+    /// Bytes that are sent as-is, and can be sent again if the request is retried
+    Bytes(&'a [u8]),
+    /// A body streamed from a reader, which can only be sent once
+    Reader(&'a mut (Read + 'a)),
+}
+
 pub trait DropboxClient {
     fn access_token(&self) -> &str;
-    fn request<T>(&self, endpoint: Endpoint, url: &str, headers: &mut BTreeMap<String, String>, body: Option<T>) -> Result<Response>
-            where T: rustc_serialize::Encodable + Clone;
+    fn request(&self, endpoint: Endpoint, url: &str, headers: &mut BTreeMap<String, String>, body: Body) -> Result<Response>;
 
+    /// Makes an RPC request, sending `body` encoded as JSON
     fn api<T>(&self, url: &str, headers: &mut BTreeMap<String, String>, body: Option<T>) -> Result<Response>
-            where T: rustc_serialize::Encodable + Clone
+            where T: rustc_serialize::Encodable
     {
-        self.request(Endpoint::Api, url, headers, body)
+        json_request(self, Endpoint::Api, url, headers, body)
     }
 
-    fn content<T>(&self, url: &str, headers: &mut BTreeMap<String, String>, body: Option<T>) -> Result<Response>
-            where T: rustc_serialize::Encodable + Clone
-    {
+    /// Makes a request to the content endpoint, used for uploads and downloads
+    fn content(&self, url: &str, headers: &mut BTreeMap<String, String>, body: Body) -> Result<Response> {
         self.request(Endpoint::Content, url, headers, body)
     }
+
+    /// Makes an RPC request to the notify endpoint, used for longpolling
+    ///
+    /// Requests to the notify endpoint must not carry the access token.
+    fn notify<T>(&self, url: &str, headers: &mut BTreeMap<String, String>, body: Option<T>) -> Result<Response>
+            where T: rustc_serialize::Encodable
+    {
+        json_request(self, Endpoint::Notify, url, headers, body)
+    }
+
+    /// How many requests this client can usefully have in flight at once
+    ///
+    /// Parallel transfers such as `files::upload_parallel` never run more requests than this.
+    fn connection_limit(&self) -> usize {
+        1
+    }
+}
+
+/// Makes a request to `endpoint`, sending `body` encoded as JSON
+fn json_request<C, T>(client: &C, endpoint: Endpoint, url: &str, headers: &mut BTreeMap<String, String>, body: Option<T>) -> Result<Response>
+        where C: DropboxClient + ?Sized,
+              T: rustc_serialize::Encodable
+{
+    let json = match body {
+        Some(ref body) => Some(try!(json::encode(body))),
+        None => None,
+    };
+    let body = match json {
+        Some(ref json) => Body::Bytes(json.as_bytes()),
+        None => Body::Empty,
+    };
+    client.request(endpoint, url, headers, body)
 }
 
 /// Collection of possible errors
+///
+/// Errors that dropbox reports for a specific route, like `RelocationError`, hold the decoded
+/// error union along with the `ErrorResponse` it came from.
 #[derive(Debug, PartialEq, Clone)]
 pub enum ApiError {
     AddFolderMemberError,
+    /// More sessions were passed to `files::upload_session_finish_batch` than dropbox commits
+    /// at once; holds how many
+    BatchTooLarge(usize),
+    /// The transfer was cancelled through its `files::Transfer`
+    Cancelled,
     ClientError,
-    CreateFolderError,
+    /// A response from dropbox couldn't be decoded; holds the decoder's message
+    DecodeError(String),
+    /// The HTTP request could not be completed
+    HttpError(String),
+    /// Reading or writing data failed
+    IoError(io::ErrorKind, String),
+    /// An asynchronous job was still in progress when waiting for it gave up; holds the job id
+    JobTimedOut(String),
+    /// Dropbox answered with a non-200 response
+    ResponseError(ErrorResponse),
+    /// The path is a folder, or was deleted, where a file was expected; holds the path
+    NotFile(String),
+    /// This crate doesn't implement the route yet; holds the route
+    Unimplemented(String),
+    /// A configured proxy url can't be used; only `http://` proxies are supported
+    ProxyError(String),
+    /// A request still failed after this many attempts; holds the error from the last one
+    RetriesExhausted(u32, Box<ApiError>),
+    /// A thread of `files::upload_parallel` stopped before every chunk was sent
+    UploadThreadPanicked,
+    /// The file is no longer at the expected revision; holds the expected and actual `rev`
+    RevisionChanged(String, String),
+    /// The downloaded contents don't match the file's `content_hash`; holds the expected and
+    /// actual hash
+    ContentHashMismatch(String, String),
+    CreateFolderError(files::CreateFolderError, ErrorResponse),
     CreateSharedLinkError,
-    DeleteError,
-    DownloadError,
+    DeleteError(files::DeleteError, ErrorResponse),
+    DownloadError(files::DownloadError, ErrorResponse),
     GetAccountError,
     GetAccountBatchError,
-    GetMetadataError,
+    GetMetadataError(files::GetMetadataError, ErrorResponse),
     GetSharedLinksError,
-    ListFolderError,
-    ListFolderContinueError,
-    ListFolderLongpollError,
+    ListFolderError(files::ListFolderError, ErrorResponse),
+    ListFolderContinueError(files::ListFolderContinueError, ErrorResponse),
+    ListFolderLongpollError(files::ListFolderLongpollError, ErrorResponse),
     ListFolderMembersContinueError,
     ListFoldersContinueError,
     ListRevisionsError,
     MountFolderError,
-    PollError,
+    PollError(files::PollError, ErrorResponse),
     PreviewError,
     RelinquishFolderMembershipError,
-    RelocationError,
+    RelocationError(files::RelocationError, ErrorResponse),
     RemoveFolderMemberError,
     RestoreError,
     RevokeSharedLinkError,
@@ -108,32 +187,179 @@ pub enum ApiError {
     UnshareFolderError,
     UpdateFolderMemberError,
     UpdateFolderPolicyError,
-    UploadError,
-    UploadSessionLookupError,
-    UploadSessionFinishError,
+    UploadError(files::UploadError, ErrorResponse),
+    UploadSessionLookupError(files::UploadSessionLookupError, ErrorResponse),
+    UploadSessionFinishError(files::UploadSessionFinishError, ErrorResponse),
+}
+
+/// Details of an error response returned by the Dropbox API
+#[derive(Debug, PartialEq, Clone)]
+pub struct ErrorResponse {
+    /// HTTP status code of the response
+    pub status: u16,
+    /// The route that was requested, e.g. `files/move`
+    pub endpoint: String,
+    /// The `error_summary` sent by dropbox, if the body had one
+    pub error_summary: Option<String>,
+    /// The route-specific error union, e.g. `{".tag": "path", "path": {".tag": "not_found"}}`
+    pub error: Option<json::Json>,
+    /// The raw response body
+    pub body: String,
+    /// Seconds to wait before retrying, from the `Retry-After` header
+    pub retry_after: Option<u64>,
+}
+
+impl ErrorResponse {
+    pub fn new(status: u16, endpoint: &str, body: &str) -> ErrorResponse {
+        let parsed = json::Json::from_str(body).ok();
+        let error_summary = parsed.as_ref()
+                                  .and_then(|j| j.find("error_summary"))
+                                  .and_then(|s| s.as_string())
+                                  .map(|s| s.to_owned());
+        let error = parsed.as_ref().and_then(|j| j.find("error")).cloned();
+        ErrorResponse {
+            status: status,
+            endpoint: endpoint.to_owned(),
+            error_summary: error_summary,
+            error: error,
+            body: body.to_owned(),
+            retry_after: None,
+        }
+    }
+
+    /// The chain of `.tag`s in the error union, outermost first
+    ///
+    /// An error of `{".tag": "to", "to": {".tag": "conflict", "conflict": {".tag": "file"}}}`
+    /// gives `["to", "conflict", "file"]`.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags = vec![];
+        let mut current = self.error.as_ref();
+        while let Some(&json::Json::Object(ref obj)) = current {
+            match obj.get(".tag").and_then(|t| t.as_string()) {
+                Some(tag) => {
+                    tags.push(tag.to_owned());
+                    current = obj.get(tag);
+                },
+                None => break,
+            }
+        }
+        tags
+    }
+
+    /// Whether the error union starts with the given `/`-separated tags, e.g. `"path/not_found"`
+    pub fn is(&self, tags: &str) -> bool {
+        let actual = self.tags();
+        let wanted = tags.split('/').collect::<Vec<_>>();
+        wanted.len() <= actual.len() && wanted.iter().zip(actual.iter()).all(|(w, a)| *w == a)
+    }
+
+    /// Decodes the error union into one of the typed route errors, e.g. `files::RelocationError`
+    pub fn decode_error<E: rustc_serialize::Decodable>(&self) -> Option<E> {
+        self.error.as_ref().and_then(|error| {
+            let mut decoder = json::Decoder::new(error.clone());
+            rustc_serialize::Decodable::decode(&mut decoder).ok()
+        })
+    }
+}
+
+/// Replaces a 409 `ResponseError` with the typed error for the route, when it can be decoded
+///
+/// The response is kept alongside the typed error, so its status, route and body aren't lost.
+fn route_error<E, F>(err: ApiError, wrap: F) -> ApiError
+            where E: rustc_serialize::Decodable,
+                  F: FnOnce(E, ErrorResponse) -> ApiError
+{
+    match err {
+        ApiError::ResponseError(e) => {
+            if e.status == 409 {
+                if let Some(typed) = e.decode_error() {
+                    return wrap(typed, e);
+                }
+            }
+            ApiError::ResponseError(e)
+        },
+        err => err,
+    }
 }
 
 impl From<rustc_serialize::json::DecoderError> for ApiError {
     fn from(e: rustc_serialize::json::DecoderError) -> ApiError {
+        ApiError::DecodeError(format!("{}", e))
+    }
+}
+
+impl From<rustc_serialize::json::EncoderError> for ApiError {
+    fn from(e: rustc_serialize::json::EncoderError) -> ApiError {
         ApiError::ClientError
     }
 }
 
+impl From<io::Error> for ApiError {
+    fn from(e: io::Error) -> ApiError {
+        ApiError::IoError(e.kind(), format!("{}", e))
+    }
+}
+
 /// Simple abstraction of a HTTP response, to allow the HTTP client to be pluggable
 ///
-/// TODO: overhaul this
-#[derive(Debug, PartialEq, Clone)]
+/// The body is not read up front: it can be streamed through the `Read` impl, or read in one go
+/// with `bytes` or `text`.
 pub struct Response {
     pub status: u16,
     pub api_result: Option<String>,
-    pub body: String,
+    pub body: Box<Read>,
+}
+
+impl Response {
+    pub fn new<R: Read + 'static>(status: u16, api_result: Option<String>, body: R) -> Response {
+        Response {
+            status: status,
+            api_result: api_result,
+            body: Box::new(body),
+        }
+    }
+
+    /// Reads the whole body
+    pub fn bytes(mut self) -> Result<Vec<u8>> {
+        let mut bytes = vec![];
+        try!(self.body.read_to_end(&mut bytes));
+        Ok(bytes)
+    }
+
+    /// Reads the whole body, which must be UTF-8
+    pub fn text(self) -> Result<String> {
+        let bytes = try!(self.bytes());
+        String::from_utf8(bytes).map_err(|e| ApiError::IoError(io::ErrorKind::InvalidData, format!("{}", e)))
+    }
+}
+
+impl Read for Response {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.body.read(buf)
+    }
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Response")
+         .field("status", &self.status)
+         .field("api_result", &self.api_result)
+         .finish()
+    }
 }
 
 pub type Result<T> = ::std::result::Result<T, ApiError>;
 
+/// Format of the timestamps the Dropbox API sends and accepts
+const DATE_FORMAT: &'static str = "%Y-%m-%dT%H:%M:%SZ";
+
 #[cfg(feature = "hyper-client")]
 /// Default implementation of the Dropbox Client
 pub mod client;
+#[cfg(feature = "hyper-client")]
+mod proxy;
+/// Module for computing the `content_hash` dropbox gives files
+pub mod content_hash;
 /// Module for performing operations on files in a dropbox account
 pub mod files;
 /// Module that holds definitions for dropbox data structures
@@ -147,8 +373,9 @@ pub mod users;
 mod tests {
     use super::client::Client;
     use super::files;
-    use super::DropboxClient;
-    use chrono::{DateTime, Local};
+    use super::{DropboxClient, ApiError, ErrorResponse, route_error};
+    use super::structs::{FolderList, Metadata, FileMetadata, MediaInfo, MediaMetadata};
+    use chrono::{DateTime, Local, UTC, TimeZone};
     use rand;
     use std::str;
     use std::env;
@@ -174,11 +401,178 @@ mod tests {
 
         let valid_looking_token = vec!["z"; 62].join("");
         let client = Client::new(&valid_looking_token).unwrap();
-        if let Ok(_) = files::list_folder(&client, "") {
-            panic!("Valid-looking but invalid token should not be accepted");
+        match files::list_folder(&client, "") {
+            Ok(_) => panic!("Valid-looking but invalid token should not be accepted"),
+            Err(ApiError::ResponseError(e)) => {
+                assert_eq!(e.status, 401);
+                assert_eq!(&e.endpoint, "files/list_folder");
+            },
+            Err(e) => panic!("Unexpected error: {:?}", e),
         }
     }
 
+    #[test]
+    fn test_error_response() {
+        let body = r#"{"error_summary": "to/conflict/file/..", "error": {".tag": "to", "to": {".tag": "conflict", "conflict": {".tag": "file"}}}}"#;
+        let e = ErrorResponse::new(409, "files/move", body);
+        assert_eq!(e.error_summary, Some("to/conflict/file/..".to_owned()));
+        assert_eq!(e.tags(), vec!["to", "conflict", "file"]);
+        assert!(e.is("to/conflict"));
+        assert!(!e.is("from_lookup/not_found"));
+
+        let typed: files::RelocationError = e.decode_error().unwrap();
+        assert_eq!(typed, files::RelocationError::To(files::WriteError::Conflict(files::WriteConflictError::File)));
+
+        match route_error(ApiError::ResponseError(e.clone()), ApiError::RelocationError) {
+            ApiError::RelocationError(typed, response) => {
+                assert_eq!(typed, files::RelocationError::To(files::WriteError::Conflict(files::WriteConflictError::File)));
+                assert_eq!(response, e);
+            },
+            other => panic!("Unexpected error: {:?}", other),
+        }
+
+        let e = ErrorResponse::new(400, "files/move", "Error in call to API function");
+        assert_eq!(e.error, None);
+        assert!(e.tags().is_empty());
+        assert_eq!(route_error(ApiError::ResponseError(e.clone()), ApiError::RelocationError),
+                   ApiError::ResponseError(e));
+    }
+
+    #[test]
+    fn test_error_unions() {
+        use super::files::*;
+
+        let lookup: LookupError = json::decode(r#"{".tag": "malformed_path", "malformed_path": "bad"}"#).unwrap();
+        assert_eq!(lookup, LookupError::MalformedPath(Some("bad".to_owned())));
+        let lookup: LookupError = json::decode(r#"{".tag": "not_found"}"#).unwrap();
+        assert_eq!(lookup, LookupError::NotFound);
+        let lookup: LookupError = json::decode(r#"{".tag": "unsupported_content_type"}"#).unwrap();
+        assert_eq!(lookup, LookupError::Other("unsupported_content_type".to_owned()));
+
+        let conflict: WriteConflictError = json::decode(r#"{".tag": "file_ancestor"}"#).unwrap();
+        assert_eq!(conflict, WriteConflictError::FileAncestor);
+        let conflict: WriteConflictError = json::decode(r#"{".tag": "symlink"}"#).unwrap();
+        assert_eq!(conflict, WriteConflictError::Other("symlink".to_owned()));
+
+        let write: WriteError = json::decode(r#"{".tag": "conflict", "conflict": {".tag": "folder"}}"#).unwrap();
+        assert_eq!(write, WriteError::Conflict(WriteConflictError::Folder));
+        let write: WriteError = json::decode(r#"{".tag": "team_folder"}"#).unwrap();
+        assert_eq!(write, WriteError::Other("team_folder".to_owned()));
+
+        let relocation: RelocationError = json::decode(r#"{".tag": "from_lookup", "from_lookup": {".tag": "not_found"}}"#).unwrap();
+        assert_eq!(relocation, RelocationError::FromLookup(LookupError::NotFound));
+        let relocation: RelocationError = json::decode(r#"{".tag": "duplicated_or_nested_paths"}"#).unwrap();
+        assert_eq!(relocation, RelocationError::Other("duplicated_or_nested_paths".to_owned()));
+
+        let delete: DeleteError = json::decode(r#"{".tag": "path_write", "path_write": {".tag": "no_write_permission"}}"#).unwrap();
+        assert_eq!(delete, DeleteError::PathWrite(WriteError::NoWritePermission));
+        let delete: DeleteError = json::decode(r#"{".tag": "too_many_write_operations"}"#).unwrap();
+        assert_eq!(delete, DeleteError::Other("too_many_write_operations".to_owned()));
+
+        let create: CreateFolderError = json::decode(r#"{".tag": "path", "path": {".tag": "conflict", "conflict": {".tag": "file"}}}"#).unwrap();
+        assert_eq!(create, CreateFolderError::Path(WriteError::Conflict(WriteConflictError::File)));
+        let create: CreateFolderError = json::decode(r#"{".tag": "other"}"#).unwrap();
+        assert_eq!(create, CreateFolderError::Other("other".to_owned()));
+
+        let download: DownloadError = json::decode(r#"{".tag": "path", "path": {".tag": "not_file"}}"#).unwrap();
+        assert_eq!(download, DownloadError::Path(LookupError::NotFile));
+        let download: DownloadError = json::decode(r#"{".tag": "unsupported_file"}"#).unwrap();
+        assert_eq!(download, DownloadError::Other("unsupported_file".to_owned()));
+
+        let metadata: GetMetadataError = json::decode(r#"{".tag": "path", "path": {".tag": "restricted_content"}}"#).unwrap();
+        assert_eq!(metadata, GetMetadataError::Path(LookupError::RestrictedContent));
+        let metadata: GetMetadataError = json::decode(r#"{".tag": "other"}"#).unwrap();
+        assert_eq!(metadata, GetMetadataError::Other("other".to_owned()));
+
+        let list: ListFolderError = json::decode(r#"{".tag": "path", "path": {".tag": "not_folder"}}"#).unwrap();
+        assert_eq!(list, ListFolderError::Path(LookupError::NotFolder));
+        let list: ListFolderError = json::decode(r#"{".tag": "other"}"#).unwrap();
+        assert_eq!(list, ListFolderError::Other("other".to_owned()));
+
+        let list: ListFolderContinueError = json::decode(r#"{".tag": "path", "path": {".tag": "not_found"}}"#).unwrap();
+        assert_eq!(list, ListFolderContinueError::Path(LookupError::NotFound));
+        let list: ListFolderContinueError = json::decode(r#"{".tag": "reset"}"#).unwrap();
+        assert_eq!(list, ListFolderContinueError::Reset);
+        let list: ListFolderContinueError = json::decode(r#"{".tag": "other"}"#).unwrap();
+        assert_eq!(list, ListFolderContinueError::Other("other".to_owned()));
+
+        let longpoll: ListFolderLongpollError = json::decode(r#"{".tag": "reset"}"#).unwrap();
+        assert_eq!(longpoll, ListFolderLongpollError::Reset);
+        let longpoll: ListFolderLongpollError = json::decode(r#"{".tag": "other"}"#).unwrap();
+        assert_eq!(longpoll, ListFolderLongpollError::Other("other".to_owned()));
+
+        let upload: UploadError = json::decode(r#"{".tag": "path", "upload_session_id": "s1",
+            "reason": {".tag": "conflict", "conflict": {".tag": "file"}}}"#).unwrap();
+        assert_eq!(upload, UploadError::Path {
+            reason: WriteError::Conflict(WriteConflictError::File),
+            upload_session_id: "s1".to_owned(),
+        });
+        let upload: UploadError = json::decode(r#"{".tag": "properties_error"}"#).unwrap();
+        assert_eq!(upload, UploadError::Other("properties_error".to_owned()));
+
+        let session: UploadSessionLookupError = json::decode(r#"{".tag": "incorrect_offset", "correct_offset": 42}"#).unwrap();
+        assert_eq!(session, UploadSessionLookupError::IncorrectOffset { correct_offset: 42 });
+        let session: UploadSessionLookupError = json::decode(r#"{".tag": "too_large"}"#).unwrap();
+        assert_eq!(session, UploadSessionLookupError::Other("too_large".to_owned()));
+
+        let finish: UploadSessionFinishError = json::decode(r#"{".tag": "lookup_failed",
+            "lookup_failed": {".tag": "incorrect_offset", "correct_offset": 7}}"#).unwrap();
+        assert_eq!(finish, UploadSessionFinishError::LookupFailed(UploadSessionLookupError::IncorrectOffset { correct_offset: 7 }));
+        let finish: UploadSessionFinishError = json::decode(r#"{".tag": "path", "path": {".tag": "insufficient_space"}}"#).unwrap();
+        assert_eq!(finish, UploadSessionFinishError::Path(WriteError::InsufficientSpace));
+        let finish: UploadSessionFinishError = json::decode(r#"{".tag": "properties_error"}"#).unwrap();
+        assert_eq!(finish, UploadSessionFinishError::Other("properties_error".to_owned()));
+
+        let poll: PollError = json::decode(r#"{".tag": "invalid_async_job_id"}"#).unwrap();
+        assert_eq!(poll, PollError::InvalidAsyncJobId);
+        let poll: PollError = json::decode(r#"{".tag": "other"}"#).unwrap();
+        assert_eq!(poll, PollError::Other("other".to_owned()));
+    }
+
+    #[test]
+    fn test_metadata_union() {
+        let list = r#"{"entries": [
+            {".tag": "folder", "name": "dir", "path_lower": "/dir", "id": "id:a"},
+            {".tag": "deleted", "name": "old", "path_lower": "/old"},
+            {".tag": "file", "name": "f", "path_lower": "/dir/f", "id": "id:b", "size": 3, "rev": "a1",
+             "client_modified": "2015-05-12T15:50:38Z", "server_modified": "2015-05-12T15:50:38Z"}
+        ], "cursor": "c", "has_more": false}"#;
+        let list: FolderList = json::decode(list).unwrap();
+        let entries = list.entries();
+        match (&entries[0], &entries[1], &entries[2]) {
+            (&Metadata::Folder(_), &Metadata::Deleted(_), &Metadata::File(ref file)) => {
+                assert_eq!(file.size(), 3);
+                assert_eq!(*file.client_modified(), UTC.ymd(2015, 5, 12).and_hms(15, 50, 38));
+            },
+            _ => panic!("wrong metadata types: {:?}", entries),
+        }
+        assert_eq!(entries[1].path_lower(), Some("/old"));
+        assert!(json::decode::<Metadata>(r#"{".tag": "link", "name": "l"}"#).is_err());
+        let unmounted: Metadata = json::decode(r#"{".tag": "folder", "name": "shared", "id": "id:e"}"#).unwrap();
+        assert_eq!(unmounted.path_lower(), None);
+        assert!(json::decode::<Metadata>(r#"{".tag": "file", "name": "f", "path_lower": "/f", "id": "id:c",
+            "size": 0, "rev": "a2", "client_modified": "yesterday", "server_modified": "2015-05-12T15:50:38Z"}"#).is_err());
+    }
+
+    #[test]
+    fn test_media_info() {
+        let file = r#"{"name": "p.jpg", "path_lower": "/p.jpg", "id": "id:d", "size": 9, "rev": "a3",
+            "client_modified": "2015-05-12T15:50:38Z", "server_modified": "2015-05-12T15:50:38Z",
+            "media_info": {".tag": "metadata", "metadata": {".tag": "photo",
+                "dimensions": {"height": 768, "width": 1024}, "time_taken": "2015-05-01T10:00:00Z"}}}"#;
+        let file: FileMetadata = json::decode(file).unwrap();
+        match file.media_info() {
+            Some(&MediaInfo::Metadata(ref photo @ MediaMetadata::Photo(_))) => {
+                assert_eq!(photo.dimensions().unwrap().width(), 1024);
+                assert_eq!(*photo.time_taken().unwrap(), UTC.ymd(2015, 5, 1).and_hms(10, 0, 0));
+                assert!(photo.location().is_none());
+            },
+            other => panic!("wrong media info: {:?}", other),
+        }
+        let pending: MediaInfo = json::decode(r#"{".tag": "pending"}"#).unwrap();
+        assert_eq!(pending, MediaInfo::Pending);
+    }
+
     #[test]
     fn test_list_rpc() {
         let access_token = match env::var(ACCESS_TOKEN) {
@@ -188,6 +582,7 @@ mod tests {
         let client = Client::new(&access_token).unwrap();
         assert!(files::create_folder(&client, "/testdir").is_ok());
         assert!(files::list_folder(&client, "/testdir").is_ok());
+        assert!(files::list_folder_get_latest_cursor(&client, "/testdir").is_ok());
         assert!(files::delete(&client, "/testdir").is_ok());
     }
 
@@ -204,21 +599,32 @@ mod tests {
         let random_path = format!("/Test/{}/{}", now, random_filename);
         let random_path_copy = format!("/Test/{}/{}copy", now, random_filename);
         let random_path_move = format!("/Test/{}/{}move", now, random_filename);
+        let random_path_session = format!("/Test/{}/{}session", now, random_filename);
         let random_contents = random_ascii_letters(20);
 
         let client = Client::new(&access_token).unwrap();
 
-        assert!(files::upload(&client, &random_contents, &random_path).is_ok());
+        assert!(files::upload(&client, random_contents.as_bytes(), &random_path).is_ok());
+        let session_options = files::UploadOptions {
+            upload_threshold: 10,
+            chunk_size: 8,
+            .. Default::default()
+        };
+        assert!(files::upload_reader(&client, random_contents.as_bytes(), &random_path_session, session_options).is_ok());
         assert!(files::get_metadata(&client, &random_path, false).is_ok());
 
         assert!(files::copy_(&client, &random_path, &random_path_copy).is_ok());
         assert!(files::move_(&client, &random_path_copy, &random_path_move).is_ok());
 
+        let entries = files::list_folder_iter(&client, &random_dir).collect::<super::Result<Vec<_>>>().unwrap();
+        assert_eq!(entries.len(), 3);
+
         let (metadata, resp) = files::download(&client, &random_path).unwrap();
-        let body: String = json::decode(&resp.body).unwrap();
-        assert_eq!(&body, &random_contents);
+        let body = resp.bytes().unwrap();
+        assert_eq!(&body[..], random_contents.as_bytes());
 
         assert!(files::delete(&client, &random_path_move).is_ok());
+        assert!(files::delete(&client, &random_path_session).is_ok());
         assert!(files::delete(&client, &random_path).is_ok());
         assert!(files::delete(&client, &random_dir).is_ok());
     }
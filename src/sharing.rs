@@ -1,7 +1,7 @@
 use super::{Result, ApiError, Response, DropboxClient};
 use std::default::Default;
 
-use structs::{FolderList, Metadata, SharingInfo, Tag};
+use structs::{FolderList, Metadata, SharingInfo};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum JobError {
@@ -35,6 +35,28 @@ pub struct AddFolderMemberOptions {
     custom_message: Option<String>,
 }
 
+impl AddFolderMemberOptions {
+    pub fn new() -> AddFolderMemberOptions {
+        Default::default()
+    }
+
+    /// Don't notify the new members by email
+    pub fn quiet(self, quiet: bool) -> AddFolderMemberOptions {
+        AddFolderMemberOptions {
+            quiet: quiet,
+            .. self
+        }
+    }
+
+    /// Message to include in the invitation
+    pub fn custom_message(self, custom_message: &str) -> AddFolderMemberOptions {
+        AddFolderMemberOptions {
+            custom_message: Some(custom_message.to_owned()),
+            .. self
+        }
+    }
+}
+
 impl Default for AddFolderMemberOptions {
     fn default() -> AddFolderMemberOptions {
         AddFolderMemberOptions {
@@ -62,6 +84,28 @@ pub struct CreateSharedLinkOptions {
     pending_upload: Option<PendingUploadMode>,
 }
 
+impl CreateSharedLinkOptions {
+    pub fn new() -> CreateSharedLinkOptions {
+        Default::default()
+    }
+
+    pub fn short_url(self, short_url: bool) -> CreateSharedLinkOptions {
+        CreateSharedLinkOptions {
+            short_url: short_url,
+            .. self
+        }
+    }
+
+    /// Create the link even if nothing is at the path yet, for a file or folder that will be
+    /// uploaded there
+    pub fn pending_upload(self, pending_upload: PendingUploadMode) -> CreateSharedLinkOptions {
+        CreateSharedLinkOptions {
+            pending_upload: Some(pending_upload),
+            .. self
+        }
+    }
+}
+
 impl Default for CreateSharedLinkOptions {
     fn default() -> CreateSharedLinkOptions {
         CreateSharedLinkOptions {
@@ -77,6 +121,9 @@ pub enum PendingUploadMode {
     Folder,
 }
 
+/// Members of a shared folder, as returned by `list_folder_members`
+///
+/// `list_folder_members` isn't implemented yet, so there are currently never any members.
 #[derive(Debug, PartialEq, Clone)]
 pub struct SharedFolderMembers {
     users: Vec<UserMembershipInfo>,
@@ -85,24 +132,73 @@ pub struct SharedFolderMembers {
     cursor: String,
 }
 
+impl SharedFolderMembers {
+    pub fn users(&self) -> &[UserMembershipInfo] {
+        &self.users
+    }
+
+    pub fn groups(&self) -> &[GroupMembershipInfo] {
+        &self.groups
+    }
+
+    pub fn invitees(&self) -> &[InviteeMembershipInfo] {
+        &self.invitees
+    }
+
+    /// Cursor for fetching the next page of members
+    pub fn cursor(&self) -> &str {
+        &self.cursor
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct UserMembershipInfo {
     access_type: AccessLevel,
     user: UserInfo,
 }
 
+impl UserMembershipInfo {
+    pub fn access_type(&self) -> &AccessLevel {
+        &self.access_type
+    }
+
+    pub fn user(&self) -> &UserInfo {
+        &self.user
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct GroupMembershipInfo {
     access_type: AccessLevel,
     group: GroupInfo,
 }
 
+impl GroupMembershipInfo {
+    pub fn access_type(&self) -> &AccessLevel {
+        &self.access_type
+    }
+
+    pub fn group(&self) -> &GroupInfo {
+        &self.group
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct InviteeMembershipInfo {
     access_type: AccessLevel,
     invitee: InviteeInfo,
 }
 
+impl InviteeMembershipInfo {
+    pub fn access_type(&self) -> &AccessLevel {
+        &self.access_type
+    }
+
+    pub fn invitee(&self) -> &InviteeInfo {
+        &self.invitee
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum AccessLevel {
     Owner,
@@ -118,6 +214,20 @@ pub struct UserInfo {
     team_member_id: String,
 }
 
+impl UserInfo {
+    pub fn account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    pub fn same_team(&self) -> bool {
+        self.same_team
+    }
+
+    pub fn team_member_id(&self) -> &str {
+        &self.team_member_id
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct GroupInfo {
     group_name: String,
@@ -127,6 +237,28 @@ pub struct GroupInfo {
     group_external_id: String,
 }
 
+impl GroupInfo {
+    pub fn group_name(&self) -> &str {
+        &self.group_name
+    }
+
+    pub fn group_id(&self) -> &str {
+        &self.group_id
+    }
+
+    pub fn member_count(&self) -> usize {
+        self.member_count
+    }
+
+    pub fn same_team(&self) -> bool {
+        self.same_team
+    }
+
+    pub fn group_external_id(&self) -> &str {
+        &self.group_external_id
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum InviteeInfo {
     Email(String),
@@ -148,6 +280,44 @@ pub struct ShareFolderOptions {
     force_async: bool,
 }
 
+impl ShareFolderOptions {
+    pub fn new() -> ShareFolderOptions {
+        Default::default()
+    }
+
+    /// Who can be a member of the folder
+    pub fn member_policy(self, member_policy: MemberPolicy) -> ShareFolderOptions {
+        ShareFolderOptions {
+            member_policy: member_policy,
+            .. self
+        }
+    }
+
+    /// Who can add and remove members
+    pub fn acl_update_policy(self, acl_update_policy: AclUpdatePolicy) -> ShareFolderOptions {
+        ShareFolderOptions {
+            acl_update_policy: acl_update_policy,
+            .. self
+        }
+    }
+
+    /// Who can use shared links to the folder's contents
+    pub fn shared_link_policy(self, shared_link_policy: SharedLinkPolicy) -> ShareFolderOptions {
+        ShareFolderOptions {
+            shared_link_policy: shared_link_policy,
+            .. self
+        }
+    }
+
+    /// Always share the folder as a background job
+    pub fn force_async(self, force_async: bool) -> ShareFolderOptions {
+        ShareFolderOptions {
+            force_async: force_async,
+            .. self
+        }
+    }
+}
+
 impl Default for ShareFolderOptions {
     fn default() -> ShareFolderOptions {
         ShareFolderOptions {
@@ -192,6 +362,33 @@ pub struct UpdateFolderPolicyOptions {
     shared_link_policy: Option<SharedLinkPolicy>,
 }
 
+impl UpdateFolderPolicyOptions {
+    pub fn new() -> UpdateFolderPolicyOptions {
+        Default::default()
+    }
+
+    pub fn member_policy(self, member_policy: MemberPolicy) -> UpdateFolderPolicyOptions {
+        UpdateFolderPolicyOptions {
+            member_policy: Some(member_policy),
+            .. self
+        }
+    }
+
+    pub fn acl_update_policy(self, acl_update_policy: AclUpdatePolicy) -> UpdateFolderPolicyOptions {
+        UpdateFolderPolicyOptions {
+            acl_update_policy: Some(acl_update_policy),
+            .. self
+        }
+    }
+
+    pub fn shared_link_policy(self, shared_link_policy: SharedLinkPolicy) -> UpdateFolderPolicyOptions {
+        UpdateFolderPolicyOptions {
+            shared_link_policy: Some(shared_link_policy),
+            .. self
+        }
+    }
+}
+
 impl Default for UpdateFolderPolicyOptions {
     fn default() -> UpdateFolderPolicyOptions {
         UpdateFolderPolicyOptions {
@@ -223,10 +420,13 @@ pub fn check_job_status<T>(client: &T, async_job_id: &str) -> Result<JobStatus>
     Ok(JobStatus::Complete)
 }
 
+/// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn check_share_job_status<T>(client: &T, async_job_id: &str) -> Result<ShareFolderJobStatus>
                 where T: DropboxClient
 {
-    Ok(ShareFolderJobStatus::Complete(Default::default()))
+    Err(ApiError::Unimplemented("sharing/check_share_job_status".to_owned()))
 }
 
 pub fn create_shared_link<T>(client: &T, path: &str) -> Result<Metadata>
@@ -235,16 +435,22 @@ pub fn create_shared_link<T>(client: &T, path: &str) -> Result<Metadata>
     create_shared_link_with_options(client, path, Default::default())
 }
 
+/// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn create_shared_link_with_options<T>(client: &T, path: &str, options: CreateSharedLinkOptions) -> Result<Metadata>
                 where T: DropboxClient
 {
-    Ok(Default::default())
+    Err(ApiError::Unimplemented("sharing/create_shared_link".to_owned()))
 }
 
+/// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn get_folder_metadata<T>(client: &T, shared_folder_id: &str) -> Result<Metadata>
                 where T: DropboxClient
 {
-    Ok(Default::default())
+    Err(ApiError::Unimplemented("sharing/get_folder_metadata".to_owned()))
 }
 
 pub fn get_shared_links<T>(client: &T, path: Option<&str>) -> Result<Vec<Metadata>>
@@ -253,6 +459,7 @@ pub fn get_shared_links<T>(client: &T, path: Option<&str>) -> Result<Vec<Metadat
     Ok(vec![])
 }
 
+/// TODO implement
 pub fn list_folder_members<T>(client: &T, shared_folder_id: &str) -> Result<SharedFolderMembers>
                 where T: DropboxClient
 {
@@ -264,6 +471,7 @@ pub fn list_folder_members<T>(client: &T, shared_folder_id: &str) -> Result<Shar
     })
 }
 
+/// TODO implement
 pub fn list_folder_members_continue<T>(client: &T, cursor: &str) -> Result<SharedFolderMembers>
                 where T: DropboxClient
 {
@@ -287,10 +495,13 @@ pub fn list_folders_continue<T>(client: &T, cursor: &str) -> Result<FolderList>
     Ok(Default::default())
 }
 
+/// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn mount_folder<T>(client: &T, shared_folder_id: &str) -> Result<Metadata>
                 where T: DropboxClient
 {
-    Ok(Default::default())
+    Err(ApiError::Unimplemented("sharing/mount_folder".to_owned()))
 }
 
 pub fn relinquish_folder_membership<T>(client: &T, shared_folder_id: &str) -> Result<()>
@@ -320,10 +531,13 @@ pub fn share_folder<T>(client: &T, path: &str) -> Result<ShareFolderLaunch>
     share_folder_with_options(client, path, Default::default())
 }
 
+/// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn share_folder_with_options<T>(client: &T, path: &str, options: ShareFolderOptions) -> Result<ShareFolderLaunch>
                 where T: DropboxClient
 {
-    Ok(ShareFolderLaunch::Complete(Default::default()))
+    Err(ApiError::Unimplemented("sharing/share_folder".to_owned()))
 }
 
 pub fn transfer_folder<T>(client: &T, shared_folder_id: &str, to_dropbox_id: &str) -> Result<()>
@@ -359,8 +573,11 @@ pub fn update_folder_policy<T>(client: &T, shared_folder_id: &str) -> Result<Met
     update_folder_policy_with_options(client, shared_folder_id, Default::default())
 }
 
+/// TODO implement
+///
+/// Always fails with `ApiError::Unimplemented`.
 pub fn update_folder_policy_with_options<T>(client: &T, shared_folder_id: &str, options: UpdateFolderPolicyOptions) -> Result<Metadata>
                 where T: DropboxClient
 {
-    Ok(Default::default())
+    Err(ApiError::Unimplemented("sharing/update_folder_policy".to_owned()))
 }
@@ -1,109 +1,216 @@
 use std::default::Default;
+use chrono::{DateTime, UTC, TimeZone};
 use rustc_serialize::{Encoder, Encodable, Decoder, Decodable};
+use rustc_serialize::json;
+use super::DATE_FORMAT;
 
+/// Metadata for an entry in a dropbox, as returned by `list_folder` and friends
+///
+/// Dropbox sends this as a union, with a `.tag` of `file`, `folder` or `deleted`.
 #[derive(Debug, PartialEq, Clone)]
-pub enum Tag {
-    File,
-    Folder,
+pub enum Metadata {
+    File(FileMetadata),
+    Folder(FolderMetadata),
+    /// Only returned when listing with `include_deleted`, or in changes since a cursor
+    Deleted(DeletedMetadata),
 }
 
-impl Encodable for Tag {
-    fn encode<S: Encoder>(&self, encoder: &mut S) -> Result<(), S::Error> {
-        encoder.emit_enum("Tag", |encoder| {
-            match *self {
-                Tag::File => encoder.emit_enum_variant("File", 0, 4, |encoder| "file".encode(encoder)),
-                Tag::Folder => encoder.emit_enum_variant("Folder", 0, 6, |encoder| "folder".encode(encoder)),
-            }
-        })
+impl Metadata {
+    pub fn name(&self) -> &str {
+        match *self {
+            Metadata::File(ref m) => &m.name,
+            Metadata::Folder(ref m) => &m.name,
+            Metadata::Deleted(ref m) => &m.name,
+        }
     }
-}
 
-impl Decodable for Tag {
-    fn decode<D: Decoder>(decoder: &mut D) -> Result<Tag, D::Error> {
-        decoder.read_enum("Tag", |decoder| {
-                decoder.read_enum_variant(&["file", "folder"], |decoder, num| {
-                    Ok(match num {
-                        0 => Tag::File,
-                        1 => Tag::Folder,
-                        _ => unreachable!(),
-                    })
-                })
-        })
+    /// Lowercased path of the entry, which is missing when it isn't mounted in the user's dropbox
+    pub fn path_lower(&self) -> Option<&str> {
+        match *self {
+            Metadata::File(ref m) => m.path_lower(),
+            Metadata::Folder(ref m) => m.path_lower(),
+            Metadata::Deleted(ref m) => m.path_lower(),
+        }
     }
 }
 
-#[derive(Debug, PartialEq, Clone, RustcEncodable)]
-pub struct Metadata {
-    tag: Tag,
-    name: String,
-    path_lower: String,
-    id: String,
-    size: Option<usize>,
-    rev: Option<String>,
-    client_modified: Option<String>,
-    server_modified: Option<String>,
-    sharing_info: Option<SharingInfo>,
+impl Encodable for Metadata {
+    fn encode<S: Encoder>(&self, encoder: &mut S) -> Result<(), S::Error> {
+        match *self {
+            Metadata::File(ref m) => encode_tagged(encoder, "file", m),
+            Metadata::Folder(ref m) => encode_tagged(encoder, "folder", m),
+            Metadata::Deleted(ref m) => encode_tagged(encoder, "deleted", m),
+        }
+    }
 }
 
 impl Decodable for Metadata {
     fn decode<D: Decoder>(decoder: &mut D) -> Result<Metadata, D::Error> {
-        decoder.read_struct("", 0, |decoder| {
-            Ok(Metadata {
-                tag: try!(decoder.read_struct_field(".tag", 0, |decoder| Decodable::decode(decoder))),
-                name: try!(decoder.read_struct_field("name", 0, |decoder| Decodable::decode(decoder))),
-                path_lower: try!(decoder.read_struct_field("path_lower", 0, |decoder| Decodable::decode(decoder))),
-                id: try!(decoder.read_struct_field("id", 0, |decoder| Decodable::decode(decoder))),
-                client_modified: try!(decoder.read_struct_field("client_modified", 0, |decoder| Decodable::decode(decoder))),
-                server_modified: try!(decoder.read_struct_field("server_modified", 0, |decoder| Decodable::decode(decoder))),
-                rev: try!(decoder.read_struct_field("rev", 0, |decoder| Decodable::decode(decoder))),
-                size: try!(decoder.read_struct_field("size", 0, |decoder| Decodable::decode(decoder))),
-                sharing_info: try!(decoder.read_struct_field("sharing_info", 0, |decoder| Decodable::decode(decoder))),
-            })
+        decoder.read_struct("Metadata", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            match &tag[..] {
+                "file" => FileMetadata::read_fields(decoder).map(Metadata::File),
+                "folder" => FolderMetadata::read_fields(decoder).map(Metadata::Folder),
+                "deleted" => DeletedMetadata::read_fields(decoder).map(Metadata::Deleted),
+                _ => Err(decoder.error(&format!("Unknown metadata type: {}", tag))),
+            }
         })
     }
 }
 
-impl Default for Metadata {
-    fn default() -> Metadata {
-        Metadata {
-            tag: Tag::File,
-            name: "".to_string(),
-            path_lower: "".to_string(),
-            id: "".to_string(),
-            size: Some(0),
-            rev: Some("".to_string()),
-            client_modified: Some("".to_string()),
-            server_modified: Some("".to_string()),
-            sharing_info: Some(SharingInfo {
-                read_only: false,
-                parent_shared_folder_id: "".to_string()
-            }),
+/// Timestamps are sent as strings like `2015-05-12T15:50:38Z`
+fn decode_date<D: Decoder>(decoder: &mut D) -> Result<DateTime<UTC>, D::Error> {
+    let date = try!(decoder.read_str());
+    UTC.datetime_from_str(&date, DATE_FORMAT)
+       .map_err(|e| decoder.error(&format!("Invalid timestamp {}: {}", date, e)))
+}
+
+fn encode_date<S: Encoder>(date: &DateTime<UTC>, encoder: &mut S) -> Result<(), S::Error> {
+    encoder.emit_str(&date.format(DATE_FORMAT).to_string())
+}
+
+fn decode_optional_date<D: Decoder>(decoder: &mut D) -> Result<Option<DateTime<UTC>>, D::Error> {
+    decoder.read_option(|decoder, present| {
+        if present {
+            decode_date(decoder).map(Some)
+        } else {
+            Ok(None)
         }
+    })
+}
+
+fn encode_optional_date<S: Encoder>(date: &Option<DateTime<UTC>>, encoder: &mut S) -> Result<(), S::Error> {
+    encoder.emit_option(|encoder| {
+        match *date {
+            Some(ref date) => encoder.emit_option_some(|encoder| encode_date(date, encoder)),
+            None => encoder.emit_option_none(),
+        }
+    })
+}
+
+/// Encodes `value` as an object with the `.tag` of its variant added, the way dropbox sends
+/// unions
+fn encode_tagged<S: Encoder, E: Encodable>(encoder: &mut S, tag: &str, value: &E) -> Result<(), S::Error> {
+    // Encoding a plain struct to JSON can't fail, and neither can reading the result back
+    let mut value = json::Json::from_str(&json::encode(value).unwrap()).unwrap();
+    if let json::Json::Object(ref mut map) = value {
+        map.insert(".tag".to_owned(), json::Json::String(tag.to_owned()));
     }
+    value.encode(encoder)
 }
 
-#[derive(PartialEq, Clone, Debug, RustcEncodable, RustcDecodable)]
+#[derive(PartialEq, Clone, Debug)]
 pub struct FileMetadata {
     name: String,
-    path_lower: String,
+    path_lower: Option<String>,
     id: String,
-    client_modified: String,
-    server_modified: String,
+    client_modified: DateTime<UTC>,
+    server_modified: DateTime<UTC>,
     rev: String,
-    size: usize,
+    size: u64,
+    content_hash: Option<String>,
     sharing_info: Option<SharingInfo>,
-    media_info: Option<()>,
+    media_info: Option<MediaInfo>,
+}
+
+impl FileMetadata {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path_lower(&self) -> Option<&str> {
+        self.path_lower.as_ref().map(|path| &path[..])
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// When the file was last modified on the client that uploaded it
+    pub fn client_modified(&self) -> &DateTime<UTC> {
+        &self.client_modified
+    }
+
+    /// When the file was last changed on dropbox
+    pub fn server_modified(&self) -> &DateTime<UTC> {
+        &self.server_modified
+    }
+
+    /// Unique identifier for this revision of the file
+    pub fn rev(&self) -> &str {
+        &self.rev
+    }
+
+    /// Size of the file in bytes
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Hash of the file's contents, as computed by `content_hash::ContentHasher`
+    pub fn content_hash(&self) -> Option<&str> {
+        self.content_hash.as_ref().map(|h| &h[..])
+    }
+
+    /// Set if the file is in a shared folder
+    pub fn sharing_info(&self) -> Option<&SharingInfo> {
+        self.sharing_info.as_ref()
+    }
+
+    /// Photo or video details, only set when asked for with `include_media_info`
+    pub fn media_info(&self) -> Option<&MediaInfo> {
+        self.media_info.as_ref()
+    }
+
+    /// Reads the fields of a file, which may also hold the `.tag` of a `Metadata`
+    fn read_fields<D: Decoder>(decoder: &mut D) -> Result<FileMetadata, D::Error> {
+        Ok(FileMetadata {
+            name: try!(decoder.read_struct_field("name", 0, Decodable::decode)),
+            path_lower: try!(decoder.read_struct_field("path_lower", 0, Decodable::decode)),
+            id: try!(decoder.read_struct_field("id", 0, Decodable::decode)),
+            client_modified: try!(decoder.read_struct_field("client_modified", 0, decode_date)),
+            server_modified: try!(decoder.read_struct_field("server_modified", 0, decode_date)),
+            rev: try!(decoder.read_struct_field("rev", 0, Decodable::decode)),
+            size: try!(decoder.read_struct_field("size", 0, Decodable::decode)),
+            content_hash: try!(decoder.read_struct_field("content_hash", 0, Decodable::decode)),
+            sharing_info: try!(decoder.read_struct_field("sharing_info", 0, Decodable::decode)),
+            media_info: try!(decoder.read_struct_field("media_info", 0, Decodable::decode)),
+        })
+    }
+}
+
+impl Encodable for FileMetadata {
+    fn encode<S: Encoder>(&self, encoder: &mut S) -> Result<(), S::Error> {
+        encoder.emit_struct("FileMetadata", 10, |encoder| {
+            try!(encoder.emit_struct_field("name", 0, |encoder| self.name.encode(encoder)));
+            try!(encoder.emit_struct_field("path_lower", 1, |encoder| self.path_lower.encode(encoder)));
+            try!(encoder.emit_struct_field("id", 2, |encoder| self.id.encode(encoder)));
+            try!(encoder.emit_struct_field("client_modified", 3, |encoder| encode_date(&self.client_modified, encoder)));
+            try!(encoder.emit_struct_field("server_modified", 4, |encoder| encode_date(&self.server_modified, encoder)));
+            try!(encoder.emit_struct_field("rev", 5, |encoder| self.rev.encode(encoder)));
+            try!(encoder.emit_struct_field("size", 6, |encoder| self.size.encode(encoder)));
+            try!(encoder.emit_struct_field("content_hash", 7, |encoder| self.content_hash.encode(encoder)));
+            try!(encoder.emit_struct_field("sharing_info", 8, |encoder| self.sharing_info.encode(encoder)));
+            encoder.emit_struct_field("media_info", 9, |encoder| self.media_info.encode(encoder))
+        })
+    }
+}
+
+impl Decodable for FileMetadata {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<FileMetadata, D::Error> {
+        decoder.read_struct("FileMetadata", 0, FileMetadata::read_fields)
+    }
 }
 
 impl Default for FileMetadata {
     fn default() -> FileMetadata {
         FileMetadata {
             name: "".to_string(),
-            path_lower: "".to_string(),
-            client_modified: "".to_string(),
-            server_modified: "".to_string(),
+            path_lower: None,
+            client_modified: UTC.timestamp(0, 0),
+            server_modified: UTC.timestamp(0, 0),
             rev: "".to_string(),
             size: 0,
+            content_hash: None,
             id: "".to_string(),
             sharing_info: None,
             media_info: None,
@@ -111,6 +218,78 @@ impl Default for FileMetadata {
     }
 }
 
+#[derive(PartialEq, Clone, Debug, RustcEncodable)]
+pub struct FolderMetadata {
+    name: String,
+    path_lower: Option<String>,
+    id: String,
+    sharing_info: Option<FolderSharingInfo>,
+}
+
+impl FolderMetadata {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path_lower(&self) -> Option<&str> {
+        self.path_lower.as_ref().map(|path| &path[..])
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Set if the folder is shared, or is inside a shared folder
+    pub fn sharing_info(&self) -> Option<&FolderSharingInfo> {
+        self.sharing_info.as_ref()
+    }
+
+    fn read_fields<D: Decoder>(decoder: &mut D) -> Result<FolderMetadata, D::Error> {
+        Ok(FolderMetadata {
+            name: try!(decoder.read_struct_field("name", 0, Decodable::decode)),
+            path_lower: try!(decoder.read_struct_field("path_lower", 0, Decodable::decode)),
+            id: try!(decoder.read_struct_field("id", 0, Decodable::decode)),
+            sharing_info: try!(decoder.read_struct_field("sharing_info", 0, Decodable::decode)),
+        })
+    }
+}
+
+impl Decodable for FolderMetadata {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<FolderMetadata, D::Error> {
+        decoder.read_struct("FolderMetadata", 0, FolderMetadata::read_fields)
+    }
+}
+
+/// An entry that used to exist, but has been deleted
+#[derive(PartialEq, Clone, Debug, RustcEncodable)]
+pub struct DeletedMetadata {
+    name: String,
+    path_lower: Option<String>,
+}
+
+impl DeletedMetadata {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path_lower(&self) -> Option<&str> {
+        self.path_lower.as_ref().map(|path| &path[..])
+    }
+
+    fn read_fields<D: Decoder>(decoder: &mut D) -> Result<DeletedMetadata, D::Error> {
+        Ok(DeletedMetadata {
+            name: try!(decoder.read_struct_field("name", 0, Decodable::decode)),
+            path_lower: try!(decoder.read_struct_field("path_lower", 0, Decodable::decode)),
+        })
+    }
+}
+
+impl Decodable for DeletedMetadata {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<DeletedMetadata, D::Error> {
+        decoder.read_struct("DeletedMetadata", 0, DeletedMetadata::read_fields)
+    }
+}
+
 /// Struct that is returned from the `list_folder` API call
 #[derive(RustcEncodable, RustcDecodable, Debug, PartialEq, Clone)]
 pub struct FolderList {
@@ -119,6 +298,26 @@ pub struct FolderList {
     has_more: bool,
 }
 
+impl FolderList {
+    pub fn entries(&self) -> &[Metadata] {
+        &self.entries
+    }
+
+    pub fn into_entries(self) -> Vec<Metadata> {
+        self.entries
+    }
+
+    /// Cursor to pass to `list_folder_continue` for the next page, or for later changes
+    pub fn cursor(&self) -> &str {
+        &self.cursor
+    }
+
+    /// Whether there are more entries to fetch with `list_folder_continue`
+    pub fn has_more(&self) -> bool {
+        self.has_more
+    }
+}
+
 impl Default for FolderList {
     fn default() -> FolderList {
         FolderList {
@@ -132,12 +331,281 @@ impl Default for FolderList {
 #[derive(PartialEq, Debug, Clone, RustcEncodable, RustcDecodable)]
 pub struct NewFolder {
     name: String,
-    path_lower: String,
+    path_lower: Option<String>,
     id: String,
 }
 
+impl NewFolder {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path_lower(&self) -> Option<&str> {
+        self.path_lower.as_ref().map(|path| &path[..])
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
 #[derive(RustcEncodable, RustcDecodable, Debug, PartialEq, Clone)]
 pub struct SharingInfo {
     read_only: bool,
     parent_shared_folder_id: String,
 }
+
+impl SharingInfo {
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// ID of the shared folder the file is in
+    pub fn parent_shared_folder_id(&self) -> &str {
+        &self.parent_shared_folder_id
+    }
+}
+
+/// Sharing details of a folder, which may be a shared folder itself or inside one
+#[derive(RustcEncodable, RustcDecodable, Debug, PartialEq, Clone)]
+pub struct FolderSharingInfo {
+    read_only: bool,
+    parent_shared_folder_id: Option<String>,
+    shared_folder_id: Option<String>,
+}
+
+impl FolderSharingInfo {
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// ID of the shared folder this folder is inside of, if any
+    pub fn parent_shared_folder_id(&self) -> Option<&str> {
+        self.parent_shared_folder_id.as_ref().map(|id| &id[..])
+    }
+
+    /// ID of this folder, if it is a shared folder itself
+    pub fn shared_folder_id(&self) -> Option<&str> {
+        self.shared_folder_id.as_ref().map(|id| &id[..])
+    }
+}
+
+/// Media details of a file, returned when `include_media_info` is set
+#[derive(Debug, PartialEq, Clone)]
+pub enum MediaInfo {
+    /// Dropbox is still extracting the details from the file
+    Pending,
+    Metadata(MediaMetadata),
+}
+
+impl Encodable for MediaInfo {
+    fn encode<S: Encoder>(&self, encoder: &mut S) -> Result<(), S::Error> {
+        match *self {
+            MediaInfo::Pending => encoder.emit_struct("MediaInfo", 1, |encoder| {
+                encoder.emit_struct_field(".tag", 0, |encoder| encoder.emit_str("pending"))
+            }),
+            MediaInfo::Metadata(ref m) => encoder.emit_struct("MediaInfo", 2, |encoder| {
+                try!(encoder.emit_struct_field(".tag", 0, |encoder| encoder.emit_str("metadata")));
+                encoder.emit_struct_field("metadata", 1, |encoder| m.encode(encoder))
+            }),
+        }
+    }
+}
+
+impl Decodable for MediaInfo {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<MediaInfo, D::Error> {
+        decoder.read_struct("MediaInfo", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            match &tag[..] {
+                "pending" => Ok(MediaInfo::Pending),
+                "metadata" => decoder.read_struct_field("metadata", 0, Decodable::decode).map(MediaInfo::Metadata),
+                _ => Err(decoder.error(&format!("Unknown media info type: {}", tag))),
+            }
+        })
+    }
+}
+
+/// Details dropbox extracted from a photo or video
+#[derive(Debug, PartialEq, Clone)]
+pub enum MediaMetadata {
+    Photo(PhotoMetadata),
+    Video(VideoMetadata),
+}
+
+impl MediaMetadata {
+    pub fn dimensions(&self) -> Option<&Dimensions> {
+        match *self {
+            MediaMetadata::Photo(ref m) => m.dimensions.as_ref(),
+            MediaMetadata::Video(ref m) => m.dimensions.as_ref(),
+        }
+    }
+
+    pub fn location(&self) -> Option<&GpsCoordinates> {
+        match *self {
+            MediaMetadata::Photo(ref m) => m.location.as_ref(),
+            MediaMetadata::Video(ref m) => m.location.as_ref(),
+        }
+    }
+
+    pub fn time_taken(&self) -> Option<&DateTime<UTC>> {
+        match *self {
+            MediaMetadata::Photo(ref m) => m.time_taken.as_ref(),
+            MediaMetadata::Video(ref m) => m.time_taken.as_ref(),
+        }
+    }
+}
+
+impl Encodable for MediaMetadata {
+    fn encode<S: Encoder>(&self, encoder: &mut S) -> Result<(), S::Error> {
+        match *self {
+            MediaMetadata::Photo(ref m) => encode_tagged(encoder, "photo", m),
+            MediaMetadata::Video(ref m) => encode_tagged(encoder, "video", m),
+        }
+    }
+}
+
+impl Decodable for MediaMetadata {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<MediaMetadata, D::Error> {
+        decoder.read_struct("MediaMetadata", 0, |decoder| {
+            let tag: String = try!(decoder.read_struct_field(".tag", 0, Decodable::decode));
+            match &tag[..] {
+                "photo" => PhotoMetadata::read_fields(decoder).map(MediaMetadata::Photo),
+                "video" => VideoMetadata::read_fields(decoder).map(MediaMetadata::Video),
+                _ => Err(decoder.error(&format!("Unknown media type: {}", tag))),
+            }
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct PhotoMetadata {
+    dimensions: Option<Dimensions>,
+    location: Option<GpsCoordinates>,
+    time_taken: Option<DateTime<UTC>>,
+}
+
+impl PhotoMetadata {
+    pub fn dimensions(&self) -> Option<&Dimensions> {
+        self.dimensions.as_ref()
+    }
+
+    /// Where the photo was taken
+    pub fn location(&self) -> Option<&GpsCoordinates> {
+        self.location.as_ref()
+    }
+
+    pub fn time_taken(&self) -> Option<&DateTime<UTC>> {
+        self.time_taken.as_ref()
+    }
+
+    fn read_fields<D: Decoder>(decoder: &mut D) -> Result<PhotoMetadata, D::Error> {
+        Ok(PhotoMetadata {
+            dimensions: try!(decoder.read_struct_field("dimensions", 0, Decodable::decode)),
+            location: try!(decoder.read_struct_field("location", 0, Decodable::decode)),
+            time_taken: try!(decoder.read_struct_field("time_taken", 0, decode_optional_date)),
+        })
+    }
+}
+
+impl Encodable for PhotoMetadata {
+    fn encode<S: Encoder>(&self, encoder: &mut S) -> Result<(), S::Error> {
+        encoder.emit_struct("PhotoMetadata", 3, |encoder| {
+            try!(encoder.emit_struct_field("dimensions", 0, |encoder| self.dimensions.encode(encoder)));
+            try!(encoder.emit_struct_field("location", 1, |encoder| self.location.encode(encoder)));
+            encoder.emit_struct_field("time_taken", 2, |encoder| encode_optional_date(&self.time_taken, encoder))
+        })
+    }
+}
+
+impl Decodable for PhotoMetadata {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<PhotoMetadata, D::Error> {
+        decoder.read_struct("PhotoMetadata", 0, PhotoMetadata::read_fields)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct VideoMetadata {
+    dimensions: Option<Dimensions>,
+    location: Option<GpsCoordinates>,
+    time_taken: Option<DateTime<UTC>>,
+    duration: Option<u64>,
+}
+
+impl VideoMetadata {
+    pub fn dimensions(&self) -> Option<&Dimensions> {
+        self.dimensions.as_ref()
+    }
+
+    /// Where the video was recorded
+    pub fn location(&self) -> Option<&GpsCoordinates> {
+        self.location.as_ref()
+    }
+
+    pub fn time_taken(&self) -> Option<&DateTime<UTC>> {
+        self.time_taken.as_ref()
+    }
+
+    /// Length of the video in milliseconds
+    pub fn duration(&self) -> Option<u64> {
+        self.duration
+    }
+
+    fn read_fields<D: Decoder>(decoder: &mut D) -> Result<VideoMetadata, D::Error> {
+        Ok(VideoMetadata {
+            dimensions: try!(decoder.read_struct_field("dimensions", 0, Decodable::decode)),
+            location: try!(decoder.read_struct_field("location", 0, Decodable::decode)),
+            time_taken: try!(decoder.read_struct_field("time_taken", 0, decode_optional_date)),
+            duration: try!(decoder.read_struct_field("duration", 0, Decodable::decode)),
+        })
+    }
+}
+
+impl Encodable for VideoMetadata {
+    fn encode<S: Encoder>(&self, encoder: &mut S) -> Result<(), S::Error> {
+        encoder.emit_struct("VideoMetadata", 4, |encoder| {
+            try!(encoder.emit_struct_field("dimensions", 0, |encoder| self.dimensions.encode(encoder)));
+            try!(encoder.emit_struct_field("location", 1, |encoder| self.location.encode(encoder)));
+            try!(encoder.emit_struct_field("time_taken", 2, |encoder| encode_optional_date(&self.time_taken, encoder)));
+            encoder.emit_struct_field("duration", 3, |encoder| self.duration.encode(encoder))
+        })
+    }
+}
+
+impl Decodable for VideoMetadata {
+    fn decode<D: Decoder>(decoder: &mut D) -> Result<VideoMetadata, D::Error> {
+        decoder.read_struct("VideoMetadata", 0, VideoMetadata::read_fields)
+    }
+}
+
+/// Size of a photo or video in pixels
+#[derive(RustcEncodable, RustcDecodable, Debug, PartialEq, Clone)]
+pub struct Dimensions {
+    height: u64,
+    width: u64,
+}
+
+impl Dimensions {
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn width(&self) -> u64 {
+        self.width
+    }
+}
+
+#[derive(RustcEncodable, RustcDecodable, Debug, PartialEq, Clone)]
+pub struct GpsCoordinates {
+    latitude: f64,
+    longitude: f64,
+}
+
+impl GpsCoordinates {
+    pub fn latitude(&self) -> f64 {
+        self.latitude
+    }
+
+    pub fn longitude(&self) -> f64 {
+        self.longitude
+    }
+}
@@ -1,8 +1,11 @@
 use super::{Result, ApiError, Response, DropboxClient};
 use std::default::Default;
 
-use structs::{FolderList, Metadata, SharingInfo, Tag};
+use structs::{FolderList, Metadata, SharingInfo};
 
+/// Public details of an account, as returned by `get_account`
+///
+/// `get_account` isn't implemented yet, so every field is currently unset.
 #[derive(Debug, PartialEq, Clone)]
 pub struct BasicAccount {
     account_id: Option<String>,
@@ -10,9 +13,26 @@ pub struct BasicAccount {
     is_teammate: Option<bool>,
 }
 
+impl BasicAccount {
+    pub fn account_id(&self) -> Option<&str> {
+        self.account_id.as_ref().map(|id| &id[..])
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(|name| &name[..])
+    }
+
+    pub fn is_teammate(&self) -> Option<bool> {
+        self.is_teammate
+    }
+}
+
+/// Details of the current user's account, as returned by `get_current_account`
+///
+/// `get_current_account` isn't implemented yet, so every field is currently empty.
 #[derive(Debug, PartialEq, Clone)]
 pub struct FullAccount {
-account_id: String,
+    account_id: String,
     name: String,
     email: String,
     locale: String,
@@ -23,14 +43,72 @@ account_id: String,
     team: Option<String>,
 }
 
+impl FullAccount {
+    pub fn account_id(&self) -> &str {
+        &self.account_id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn email(&self) -> &str {
+        &self.email
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn referral_link(&self) -> &str {
+        &self.referral_link
+    }
+
+    /// Whether the account has a paired work or personal account
+    pub fn is_paired(&self) -> bool {
+        self.is_paired
+    }
+
+    /// `basic`, `pro` or `business`
+    pub fn account_type(&self) -> &str {
+        &self.account_type
+    }
+
+    pub fn country(&self) -> &str {
+        &self.country
+    }
+
+    pub fn team(&self) -> Option<&str> {
+        self.team.as_ref().map(|team| &team[..])
+    }
+}
+
+/// How much space the current user has, as returned by `get_space_usage`
+///
+/// `get_space_usage` isn't implemented yet, so both counts are currently zero.
 #[derive(Debug, PartialEq, Clone)]
 pub struct SpaceUsage {
-    used: usize,
-    allocation: usize,
+    used: u64,
+    allocation: u64,
+}
+
+impl SpaceUsage {
+    /// Bytes in use
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
+    /// Bytes available to the account
+    pub fn allocation(&self) -> u64 {
+        self.allocation
+    }
 }
 
 // Functions
 
+/// TODO implement
+///
+/// Always returns an account with every field unset.
 pub fn get_account<T>(client: &T, account_id: &str) -> Result<BasicAccount>
                 where T: DropboxClient
 {
@@ -41,6 +119,9 @@ pub fn get_account<T>(client: &T, account_id: &str) -> Result<BasicAccount>
     })
 }
 
+/// TODO implement
+///
+/// Always returns no accounts, or an error for more than 300 ids.
 pub fn get_account_batch<T, U>(client: &T, account_ids: &[U]) -> Result<Vec<BasicAccount>>
                 where T: DropboxClient,
                       U: AsRef<str>
@@ -51,6 +132,9 @@ pub fn get_account_batch<T, U>(client: &T, account_ids: &[U]) -> Result<Vec<Basi
     Ok(vec![])
 }
 
+/// TODO implement
+///
+/// Always returns an account with every field empty.
 pub fn get_current_account<T>(client: &T) -> Result<FullAccount>
                 where T: DropboxClient
 {
@@ -67,6 +151,9 @@ pub fn get_current_account<T>(client: &T) -> Result<FullAccount>
     })
 }
 
+/// TODO implement
+///
+/// Always returns zero for both counts.
 pub fn get_space_usage<T>(client: &T) -> Result<SpaceUsage>
                 where T: DropboxClient
 {